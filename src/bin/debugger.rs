@@ -0,0 +1,72 @@
+use std::io::{self, BufRead, Write};
+use structopt::StructOpt;
+
+use aoc19::intcode::ProgramArgs;
+use aoc19::intcode::debugger::{Debugger, DEFAULT_HISTORY};
+
+/// Steps through an Intcode program interactively.
+#[derive(Debug, StructOpt)]
+struct Args {
+    #[structopt(flatten)]
+    program: ProgramArgs,
+
+    /// How many instructions back the back and rewind commands can go.
+    /// Defaults to a million.
+    #[structopt(long = "history")]
+    history: Option<usize>,
+
+    /// Keep every instruction's history, however long the session runs.
+    #[structopt(long = "unlimited-history", conflicts_with = "history")]
+    unlimited_history: bool
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::from_args();
+    let machine = match args.program.load() {
+        Ok(machine) => machine,
+        Err(e) => {
+            eprintln!("Couldn't load {}: {}", args.program.program.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let history = if args.unlimited_history {
+        None
+    } else {
+        Some(args.history.unwrap_or(DEFAULT_HISTORY))
+    };
+    let mut debugger = Debugger::with_history(machine, history);
+    println!("{}", debugger.current_line());
+
+    let stdin = io::stdin();
+    let mut last_command = String::new();
+    loop {
+        print!("(icdb) ");
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        // An empty line repeats the last command, which makes stepping
+        // through a program a lot less tedious.
+        let command = if line.trim().is_empty() {
+            last_command.clone()
+        } else {
+            line.trim().to_string()
+        };
+
+        if command == "quit" || command == "q" {
+            break;
+        }
+
+        match debugger.execute(&command) {
+            Ok(output) => if !output.is_empty() { println!("{}", output) },
+            Err(e) => println!("Error: {}", e)
+        }
+        last_command = command;
+    }
+
+    Ok(())
+}
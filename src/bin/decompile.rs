@@ -0,0 +1,21 @@
+use structopt::StructOpt;
+
+use aoc19::intcode::ProgramArgs;
+
+/// Decompiles an Intcode program to C-like pseudocode.
+#[derive(Debug, StructOpt)]
+struct Args {
+    #[structopt(flatten)]
+    program: ProgramArgs
+}
+
+fn main() {
+    let args = Args::from_args();
+    match args.program.load() {
+        Ok(machine) => println!("{}", machine.decompile()),
+        Err(e) => {
+            eprintln!("Couldn't load {}: {}", args.program.program.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use structopt::StructOpt;
+
+use aoc19::intcode::ProgramArgs;
+use aoc19::intcode::transpiler::transpile;
+
+/// Transpiles an Intcode program to a Rust module, written to stdout.
+#[derive(Debug, StructOpt)]
+struct Args {
+    #[structopt(flatten)]
+    program: ProgramArgs,
+
+    /// Path to this crate from wherever the module is going, `crate` if
+    /// it's going inside it.
+    #[structopt(long = "crate-path", default_value = "aoc19")]
+    crate_path: String
+}
+
+fn main() {
+    let args = Args::from_args();
+    match args.program.load() {
+        Ok(machine) => print!("{}", transpile(&machine.analysed_memory(), &args.crate_path)),
+        Err(e) => {
+            eprintln!("Couldn't load {}: {}", args.program.program.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,57 +1,88 @@
+use std::thread;
+use std::time::Duration;
+
 use itertools::Itertools;
 
 use crate::problem::Problem;
-use crate::intcode::{Machine, MachineState};
-
-fn run_amplifiers(base_machine: &Machine, phase_settings: Vec<isize>) -> isize {
-    let mut signal = 0;
-    for setting in phase_settings {
-        let mut machine = base_machine.clone();
-        machine.write(setting);
-        machine.write(signal);
-        machine.run().unwrap();
-        signal = machine.read()[0];
-    }
-    signal
-}
+use crate::intcode::Machine;
+use crate::intcode::topology::{NodeId, Topology, TopologyOutcome};
 
-fn run_amplifiers_looped(base_machine: &Machine,
-                         phase_settings: Vec<isize>) -> isize {
-    let mut machines: Vec<Machine> = phase_settings
+// How long the threaded search gets before giving up on the amplifiers.
+// The real programs take milliseconds.
+const THREADED_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn amplifiers(base_machine: &Machine, phase_settings: Vec<isize>) -> Vec<Machine> {
+    phase_settings
         .iter()
         .map(|setting| {
             let mut m = base_machine.clone();
             m.write(*setting);
             m
         })
-        .collect();
-    machines[0].write(0);
-
-    let length = machines.len();
-
-    let mut next_inputs = Vec::new();
-    let mut i = 0;
-
-    loop {
-        let machine = machines.get_mut(i).unwrap();
-        for val in next_inputs {
-            machine.write(val);
-        }
+        .collect()
+}
 
-        machine.run().unwrap();
-        next_inputs = machine.read();
+// One set of amplifiers, wired up in a line or a loop. With threaded they
+// each get a thread.
+fn thrust(machine: &Machine, setting: Vec<isize>, looped: bool, threaded: bool) -> isize {
+    let mut topology = Topology::new();
+    let nodes: Vec<NodeId> = amplifiers(machine, setting).into_iter()
+        .map(|amplifier| topology.add(amplifier))
+        .collect();
+    for pair in nodes.windows(2) {
+        topology.connect(pair[0], pair[1]);
+    }
+    if looped {
+        topology.connect(nodes[nodes.len() - 1], nodes[0]);
+    }
+    topology.write(nodes[0], 0);
+
+    let outcome = if threaded {
+        topology.run_threaded(THREADED_TIMEOUT)
+    } else {
+        topology.run()
+    };
+    match outcome.unwrap() {
+        TopologyOutcome::Halted => {},
+        outcome => panic!("The amplifiers didn't all halt: {:?}", outcome)
+    }
 
-        if machine.state() == MachineState::Halted && i == length - 1 {
-            break;
-        }
+    *topology.last_output(nodes[nodes.len() - 1]).unwrap()
+}
 
-        i = (i + 1) % length;
+// Threaded, the permutations get split between about as many workers as
+// there are cores, each going through its share one set of amplifiers at a
+// time. That's still five threads per worker, but most of them are waiting
+// on input at any given moment.
+fn max_thrust(machine: &Machine, settings: Vec<Vec<isize>>, looped: bool, threaded: bool) -> isize {
+    if !threaded {
+        return settings.into_iter()
+            .map(|setting| thrust(machine, setting, looped, false))
+            .max().unwrap();
     }
 
-    next_inputs[next_inputs.len() - 1]
+    let workers = thread::available_parallelism().map_or(1, |n| n.get());
+    let share = settings.len().div_ceil(workers);
+    thread::scope(|scope| {
+        let handles: Vec<_> = settings.chunks(share)
+            .map(|chunk| scope.spawn(move || {
+                chunk.iter()
+                    .map(|setting| thrust(machine, setting.clone(), looped, true))
+                    .max().unwrap()
+            }))
+            .collect();
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .max().unwrap()
+    })
 }
 
-pub struct DaySeven {}
+pub struct DaySeven {
+    // Runs the permutations in parallel, with every amplifier on its own
+    // thread. Only worth it for programs a lot slower than the real ones,
+    // which are done in no time either way.
+    pub threaded: bool
+}
 
 impl Problem for DaySeven {
     fn name(&self) -> String {
@@ -60,20 +91,16 @@ impl Problem for DaySeven {
 
     fn part_one(&self, input: &str) -> String {
         let machine = Machine::from_str(input).unwrap();
-        let phase_settings = (0..5).permutations(5);
-        let max_thrust = phase_settings
-            .map(|setting| run_amplifiers(&machine, setting))
-            .max().unwrap();
+        let phase_settings = (0..5).permutations(5).collect();
+        let max_thrust = max_thrust(&machine, phase_settings, false, self.threaded);
 
         max_thrust.to_string()
     }
 
     fn part_two(&self, input: &str) -> String {
         let machine = Machine::from_str(input).unwrap();
-        let phase_settings = (5..10).permutations(5);
-        let max_thrust = phase_settings
-            .map(|setting| run_amplifiers_looped(&machine, setting))
-            .max().unwrap();
+        let phase_settings = (5..10).permutations(5).collect();
+        let max_thrust = max_thrust(&machine, phase_settings, true, self.threaded);
 
         max_thrust.to_string()
     }
@@ -82,4 +109,16 @@ impl Problem for DaySeven {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn threaded_matches_single_threaded() {
+        let pipeline = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let looped = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+
+        for threaded in &[false, true] {
+            let day = DaySeven { threaded: *threaded };
+            assert_eq!("43210", day.part_one(pipeline));
+            assert_eq!("139629729", day.part_two(looped));
+        }
+    }
 }
@@ -1,15 +1,14 @@
 use crate::problem::Problem;
 use crate::intcode::Machine;
+use crate::intcode::ascii::Ascii;
 
 pub struct DaySeventeen {}
 
 impl Problem for DaySeventeen {
     fn part_one(&self, input: &str) -> String {
-        let mut machine = Machine::from_str(input).unwrap();
-        machine.run().unwrap();
-        for value in machine.read() {
-            print!("{}", std::char::from_u32(value as u32).unwrap());
-        }
+        let mut camera = Ascii::new(Machine::from_str(input).unwrap());
+        camera.run().unwrap();
+        print!("{}", camera.read_text());
         "5740   (solved manually by just printing and looking)".to_string()
     }
 
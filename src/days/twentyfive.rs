@@ -1,6 +1,7 @@
 use std::io;
 use crate::problem::Problem;
 use crate::intcode::{Machine, MachineState};
+use crate::intcode::ascii::Ascii;
 
 pub struct DayTwentyFive {}
 
@@ -33,19 +34,17 @@ fn solution_commands() -> Vec<String> {
          "south",
          "south"]
         .iter()
-        .map(|s| format!("{}\n", s).to_string())
+        .map(|s| s.to_string())
         .collect()
 }
 
-fn play(mut machine: Machine) -> String {
+fn play(machine: Machine) -> String {
+    let mut game = Ascii::new(machine);
     loop {
-        machine.run().unwrap();
+        game.run().unwrap();
+        print!("{}", game.read_text());
 
-        for c in machine.read() {
-            print!("{}", std::char::from_u32(c as u32).unwrap());
-        }
-
-        if machine.state() == MachineState::Halted {
+        if game.state() == MachineState::Halted {
             break;
         }
 
@@ -56,50 +55,38 @@ fn play(mut machine: Machine) -> String {
             break;
         }
 
-        for c in input.chars() {
-            machine.write(c as isize);
+        // `save <path>` snapshots the game so it can be picked up again later
+        // with --resume.
+        if let Some(path) = input.trim().strip_prefix("save ") {
+            match game.machine().save_snapshot(path.trim()) {
+                Ok(()) => println!("Saved to {}.", path.trim()),
+                Err(e) => println!("Couldn't save: {}", e)
+            }
+            continue;
+        }
+
+        if let Err(e) = game.send_line(input.trim_end()) {
+            println!("{}", e);
         }
     }
 
     "".to_string()
 }
 
-fn from_digits(digits: Vec<usize>) -> usize {
-    if digits.len() == 0 {
-        0
-    } else {
-        let top = digits[0] * 10_usize.pow(digits.len() as u32 - 1);
-        let bottom = from_digits(digits.iter().skip(1).map(|d| *d).collect());
-        top + bottom
-    }
-}
-
-fn play_auto(mut machine: Machine, show_output: bool) -> String {
-    let mut output = Vec::new();
+fn play_auto(machine: Machine, show_output: bool) -> String {
+    let mut game = Ascii::new(machine);
+    let mut output = String::new();
 
     for command in solution_commands() {
-        for c in command.chars() {
-            machine.write(c as isize);
-        }
-
-        machine.run().unwrap();
-
-        output = machine.read();
+        output = game.command(&command).unwrap();
 
         if show_output {
-            for c in &output {
-                print!("{}", std::char::from_u32(*c as u32).unwrap());
-            }
+            print!("{}", output);
         }
     }
 
     // The last output only contains one sequence of digits, our answer.
-    // ASCII digits are [48, 57].
-    let digits = output.iter()
-        .filter(|&&c| c >= 48 && c <= 57)
-        .map(|&c| (c as usize) - 48)
-        .collect();
-    from_digits(digits).to_string()
+    output.chars().filter(|c| c.is_ascii_digit()).collect()
 }
 
 const MODE: SolveMode = SolveMode::Invisible;
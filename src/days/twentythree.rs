@@ -1,14 +1,50 @@
+use std::sync::{Arc, Mutex};
+
 use crate::problem::Problem;
+use crate::intcode::Machine;
+use crate::intcode::network::{Nat, Network, RoundOutcome};
+
+const NETWORK_SIZE: usize = 50;
+const NAT_ADDRESS: usize = 255;
+
+fn network(input: &str) -> (Network, Arc<Mutex<Nat>>) {
+    let machine = Machine::from_str(input).unwrap();
+    let mut network = Network::new(&machine, NETWORK_SIZE);
+    let nat = Arc::new(Mutex::new(Nat::default()));
+    network.hook(NAT_ADDRESS, nat.clone());
+    (network, nat)
+}
 
 pub struct DayTwentyThree {}
 
 impl Problem for DayTwentyThree {
+    fn name(&self) -> String {
+        "Category Six".to_string()
+    }
+
     fn part_one(&self, input: &str) -> String {
-        format!("{}", "Part one not yet implemented.")
+        let (mut network, nat) = network(input);
+        loop {
+            network.round().unwrap();
+            if let Some(packet) = nat.lock().unwrap().received().first() {
+                return packet.y.to_string();
+            }
+        }
     }
 
     fn part_two(&self, input: &str) -> String {
-        format!("{}", "Part two not yet implemented.")
+        let (mut network, nat) = network(input);
+        loop {
+            if network.round().unwrap() != RoundOutcome::Idle {
+                continue;
+            }
+
+            let nat = nat.lock().unwrap();
+            let sent = nat.sent();
+            if sent.len() >= 2 && sent[sent.len() - 1].y == sent[sent.len() - 2].y {
+                return sent[sent.len() - 1].y.to_string();
+            }
+        }
     }
 }
 
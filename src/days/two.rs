@@ -3,7 +3,8 @@ use std::error::Error;
 use std::fmt;
 
 use crate::problem::Problem;
-use crate::intcode::Machine;
+use crate::intcode::{Machine, RunOutcome, Value};
+use crate::intcode::symbolic::{Expr, SymbolicError};
 
 pub struct DayTwo {}
 
@@ -27,20 +28,78 @@ impl Problem for DayTwo {
         let machine = Machine::from_str(input).unwrap();
         let target = 19690720;
 
-        // This is really dumb but I gotta go to work.
-        for noun in 0..100 {
-            for verb in 0..100 {
-                let mut candidate = machine.clone();
-                candidate.set(1, noun).unwrap();
-                candidate.set(2, verb).unwrap();
-
-                candidate.run().unwrap();
-                if *candidate.get(0).unwrap() == target {
-                    return format!("{}", 100 * noun + verb);
-                }
+        let solution = match solve(&machine, target) {
+            Ok(solution) => solution,
+            Err(e) => {
+                eprintln!("Couldn't solve for noun and verb, trying them all instead: {}", e);
+                brute_force(&machine, target)
+            }
+        };
+
+        match solution {
+            Some((noun, verb)) => format!("{}", 100 * noun + verb),
+            None => format!("{}", "No solution found under 100")
+        }
+    }
+}
+
+// Output is linear in noun and verb, so it can be solved for directly.
+fn solve(machine: &Machine, target: Value) -> Result<Option<(Value, Value)>, SymbolicError> {
+    let mut symbolic = machine.symbolic();
+    symbolic.set(1, Expr::variable("noun"))?;
+    symbolic.set(2, Expr::variable("verb"))?;
+
+    match symbolic.run()? {
+        RunOutcome::Halted => {},
+        outcome => return Err(SymbolicError::NotHalted(outcome))
+    }
+
+    let solution = symbolic.get(0).solve(target, &[("noun", 0..=99), ("verb", 0..=99)])?;
+    Ok(solution.map(|solution| (solution[0], solution[1])))
+}
+
+// This is really dumb but I gotta go to work.
+fn brute_force(machine: &Machine, target: Value) -> Option<(Value, Value)> {
+    for noun in 0..100 {
+        for verb in 0..100 {
+            let mut candidate = machine.clone();
+            candidate.set(1, noun).unwrap();
+            candidate.set(2, verb).unwrap();
+
+            candidate.run().unwrap();
+            if *candidate.get(0).unwrap() == target {
+                return Some((noun, verb));
             }
         }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solving_matches_brute_force() {
+        // Like the real inputs, the first instruction adds whatever noun
+        // and verb point at before it's all overwritten with 100*noun + verb
+        // + 19690000.
+        let machine = Machine::from_str("1,0,0,3,1002,1,100,3,1,3,2,3,1001,3,19690000,0,99").unwrap();
+
+        assert_eq!(Some((7, 20)), solve(&machine, 19690720).unwrap());
+        assert_eq!(Some((7, 20)), brute_force(&machine, 19690720));
+        assert_eq!("720", DayTwo {}.part_two("1,0,0,3,1002,1,100,3,1,3,2,3,1001,3,19690000,0,99"));
+        assert_eq!(None, solve(&machine, 1).unwrap());
+    }
+
+    #[test]
+    fn unsolvable_programs() {
+        // The day 2 example reads from noun and verb as addresses.
+        let machine = Machine::from_str("1,9,10,3,2,3,11,0,99,30,40,50").unwrap();
+        assert!(matches!(solve(&machine, 3500), Err(SymbolicError::NotLinear(_))));
 
-        format!("{}", "No solution found under 100")
+        let blocks = Machine::from_str("1101,0,0,3,3,7,99,0").unwrap();
+        assert!(matches!(solve(&blocks, 0), Err(SymbolicError::NotHalted(RunOutcome::Blocked))));
     }
 }
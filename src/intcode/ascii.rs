@@ -0,0 +1,176 @@
+use thiserror::Error;
+
+use super::{Machine, MachineState, OperationalError, RunOutcome, Value};
+use super::number::Number;
+
+#[derive(Debug, Error)]
+pub enum AsciiError {
+    #[error("`{0}` can't be sent to an ASCII program, it isn't ASCII.")]
+    NonAsciiInput(char),
+    #[error("Output `{0}` is not an ASCII character.")]
+    NonAsciiOutput(String),
+    #[error("{0}")]
+    Machine(#[from] OperationalError)
+}
+
+// The character a value stands for in an ASCII program's output, if it
+// stands for one at all.
+pub fn to_ascii<V: Number>(value: &V) -> Option<char> {
+    value.to_value()
+        .filter(|value| (0..=127).contains(value))
+        .map(|value| value as u8 as char)
+}
+
+// For programs that talk in text, which is most of the ones from day 17 on.
+// They mostly output ASCII, but usually finish with one big number, the
+// answer, that isn't. Output gets split up as it's read: characters go into
+// the text and everything else is kept aside for read_values, so neither
+// gets lost whichever one is read first.
+#[derive(Debug, Clone)]
+pub struct Ascii<V = Value> {
+    machine: Machine<V>,
+    text: String,
+    values: Vec<V>
+}
+
+impl<V: Number> Ascii<V> {
+    pub fn new(machine: Machine<V>) -> Ascii<V> {
+        Ascii { machine, text: String::new(), values: Vec::new() }
+    }
+
+    pub fn machine(&self) -> &Machine<V> {
+        &self.machine
+    }
+
+    pub fn machine_mut(&mut self) -> &mut Machine<V> {
+        &mut self.machine
+    }
+
+    // Anything read from the machine but not from this yet is dropped.
+    pub fn into_machine(self) -> Machine<V> {
+        self.machine
+    }
+
+    pub fn run(&mut self) -> Result<RunOutcome, OperationalError> {
+        self.machine.run()
+    }
+
+    pub fn state(&self) -> MachineState {
+        self.machine.state()
+    }
+
+    // Nothing is sent unless the whole string is ASCII, so a bad character
+    // can't leave half a command sitting in the machine's input.
+    pub fn send(&mut self, text: &str) -> Result<(), AsciiError> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(AsciiError::NonAsciiInput(c));
+        }
+        for byte in text.bytes() {
+            self.machine.write(V::from_value(byte as Value));
+        }
+        Ok(())
+    }
+
+    // Every ASCII program wants its commands newline terminated.
+    pub fn send_line(&mut self, line: &str) -> Result<(), AsciiError> {
+        self.send(line)?;
+        self.send("\n")
+    }
+
+    // Sends a line, runs until the program halts or wants more input, and
+    // returns whatever text it printed in between.
+    pub fn command(&mut self, line: &str) -> Result<String, AsciiError> {
+        self.send_line(line)?;
+        self.run()?;
+        Ok(self.read_text())
+    }
+
+    fn collect(&mut self) {
+        for value in self.machine.read() {
+            match to_ascii(&value) {
+                Some(c) => self.text.push(c),
+                None => self.values.push(value)
+            }
+        }
+    }
+
+    // All the text output since the last read, leaving out anything that
+    // isn't a character.
+    pub fn read_text(&mut self) -> String {
+        self.collect();
+        std::mem::take(&mut self.text)
+    }
+
+    // All the output since the last read that isn't a character.
+    pub fn read_values(&mut self) -> Vec<V> {
+        self.collect();
+        std::mem::take(&mut self.values)
+    }
+
+    // Like read_text, but for when the program should only have printed
+    // text. Fails on the first value that isn't a character, which is left
+    // for read_values along with any others.
+    pub fn expect_text(&mut self) -> Result<String, AsciiError> {
+        self.collect();
+        match self.values.first() {
+            Some(value) => Err(AsciiError::NonAsciiOutput(value.to_string())),
+            None => Ok(std::mem::take(&mut self.text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Echoes each input back until it reads a zero, then outputs 1000.
+    fn echo() -> Machine {
+        Machine::from_slots(vec![3, 17, 1005, 17, 9, 104, 1000, 99, 0, 4, 17, 1105, 1, 0, 0, 0, 0, 0])
+    }
+
+    #[test]
+    fn sends_and_reads_lines() -> Result<(), AsciiError> {
+        let mut ascii = Ascii::new(echo());
+        assert_eq!("Hello\n", ascii.command("Hello")?);
+        assert_eq!("there\n", ascii.command("there")?);
+        assert_eq!(MachineState::Blocked, ascii.state());
+        assert!(ascii.read_values().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn separates_values_from_text() -> Result<(), AsciiError> {
+        let mut ascii = Ascii::new(echo());
+        ascii.send("ok")?;
+        ascii.machine_mut().write(0);
+        ascii.run()?;
+
+        assert_eq!(vec![1000], ascii.read_values());
+        assert_eq!("ok", ascii.read_text());
+        assert_eq!(MachineState::Halted, ascii.state());
+
+        Ok(())
+    }
+
+    #[test]
+    fn expecting_text() -> Result<(), AsciiError> {
+        let mut ascii = Ascii::new(echo());
+        ascii.send("a")?;
+        ascii.machine_mut().write(-1);
+        ascii.run()?;
+
+        assert!(matches!(ascii.expect_text(), Err(AsciiError::NonAsciiOutput(value)) if value == "-1"));
+        assert_eq!(vec![-1], ascii.read_values());
+        assert_eq!("a", ascii.expect_text()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_non_ascii_input() {
+        let mut ascii = Ascii::new(echo());
+        assert!(matches!(ascii.send_line("café"), Err(AsciiError::NonAsciiInput('é'))));
+        assert!(ascii.machine().pending_input().is_empty());
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::{Address, Value, Machine, Instruction, Opcode, Parameter, ParameterMode};
+use super::disassembler;
+
+// The assembly format is the same one the disassembler prints, so anything
+// from Machine::listing assembles back to the same memory. On top of that it
+// understands labels and comments:
+//
+//     ; Echo one input back out.
+//     start:  IN   [value]
+//             OUT  [value]
+//             JT   #1, #start
+//     value:  DATA 0
+//
+// Operands are `[x]` for positional, `#x` for immediate and `rb+x` or `rb-x`
+// for relative mode, where x is a number, a label, or a label plus or minus a
+// number. A numeric prefix like `12:` is treated as the address the line
+// should start at and is checked rather than defining a label.
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AssemblyError {
+    #[error("Line {0}: `{1}` is not a known mnemonic.")]
+    UnknownMnemonic(usize, String),
+    #[error("Line {0}: `{1}` takes {2} operands, not {3}.")]
+    WrongOperandCount(usize, String, usize, usize),
+    #[error("Line {0}: `{1}` is not a valid operand.")]
+    InvalidOperand(usize, String),
+    #[error("Line {0}: `{1}` is not a valid label.")]
+    InvalidLabel(usize, String),
+    #[error("Line {0}: label `{1}` is already defined.")]
+    DuplicateLabel(usize, String),
+    #[error("Line {0}: label `{1}` is never defined.")]
+    UndefinedLabel(usize, String),
+    #[error("Line {0}: expected to be at address {1} but was at {2}.")]
+    AddressMismatch(usize, Address, Address),
+    #[error("Line {0}: cannot use an immediate mode operand as an address to set.")]
+    ImmediateModeStorage(usize),
+    #[error("Line {0}: label `{1}` plus {2} is too big to be a value.")]
+    OffsetOverflow(usize, String, Value)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Expression {
+    label: Option<String>,
+    offset: Value
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Operand {
+    mode: ParameterMode,
+    expression: Expression
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction { opcode: Opcode, operands: Vec<Operand> },
+    Data(Vec<Expression>)
+}
+
+impl Statement {
+    fn width(&self) -> usize {
+        match self {
+            Statement::Instruction { opcode, .. } => opcode.parameter_count() + 1,
+            Statement::Data(values) => values.len()
+        }
+    }
+}
+
+fn is_label(token: &str) -> bool {
+    let mut chars = token.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        },
+        _ => false
+    }
+}
+
+fn parse_expression(line: usize, token: &str) -> Result<Expression, AssemblyError> {
+    let token = token.trim();
+    let invalid = || AssemblyError::InvalidOperand(line, token.to_string());
+
+    if let Ok(offset) = token.parse::<Value>() {
+        return Ok(Expression { label: None, offset });
+    }
+
+    // A label and at most one offset, which is a sign then just digits.
+    let (label, offset) = match token.find(['+', '-']) {
+        Some(split) => {
+            let digits = token[split + 1..].trim();
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let offset = format!("{}{}", &token[split..split + 1], digits).parse::<Value>()
+                .map_err(|_| invalid())?;
+            (token[..split].trim(), offset)
+        },
+        None => (token, 0)
+    };
+
+    if !is_label(label) {
+        return Err(invalid());
+    }
+
+    Ok(Expression { label: Some(label.to_string()), offset })
+}
+
+fn parse_operand(line: usize, token: &str) -> Result<Operand, AssemblyError> {
+    let token = token.trim();
+
+    if token.starts_with('[') && token.ends_with(']') {
+        Ok(Operand {
+            mode: ParameterMode::Positional,
+            expression: parse_expression(line, &token[1..token.len() - 1])?
+        })
+    } else if let Some(rest) = token.strip_prefix('#') {
+        Ok(Operand {
+            mode: ParameterMode::Immediate,
+            expression: parse_expression(line, rest)?
+        })
+    } else if let Some(rest) = token.strip_prefix("rb") {
+        let rest = rest.trim_start();
+        let expression = match rest.strip_prefix('+') {
+            Some(positive) => parse_expression(line, positive)?,
+            None if rest.starts_with('-') => parse_expression(line, rest)?,
+            None => return Err(AssemblyError::InvalidOperand(line, token.to_string()))
+        };
+        Ok(Operand { mode: ParameterMode::Relative, expression })
+    } else {
+        Err(AssemblyError::InvalidOperand(line, token.to_string()))
+    }
+}
+
+fn parse_statement(line: usize, text: &str) -> Result<Statement, AssemblyError> {
+    let (mnemonic, rest) = match text.find(char::is_whitespace) {
+        Some(split) => (&text[..split], text[split..].trim()),
+        None => (text, "")
+    };
+    let tokens: Vec<&str> = if rest.is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',').collect()
+    };
+
+    if mnemonic.eq_ignore_ascii_case("DATA") {
+        let values = tokens.iter()
+            .map(|token| parse_expression(line, token))
+            .collect::<Result<Vec<Expression>, AssemblyError>>()?;
+        return Ok(Statement::Data(values));
+    }
+
+    let opcode = Opcode::from_mnemonic(mnemonic)
+        .ok_or_else(|| AssemblyError::UnknownMnemonic(line, mnemonic.to_string()))?;
+
+    if tokens.len() != opcode.parameter_count() {
+        return Err(AssemblyError::WrongOperandCount(
+            line, mnemonic.to_string(), opcode.parameter_count(), tokens.len()
+        ));
+    }
+
+    let operands = tokens.iter()
+        .map(|token| parse_operand(line, token))
+        .collect::<Result<Vec<Operand>, AssemblyError>>()?;
+
+    if opcode.writes_last_parameter() &&
+       operands.last().map(|operand| operand.mode) == Some(ParameterMode::Immediate) {
+        return Err(AssemblyError::ImmediateModeStorage(line));
+    }
+
+    Ok(Statement::Instruction { opcode, operands })
+}
+
+fn resolve(line: usize,
+           expression: &Expression,
+           labels: &HashMap<String, Address>) -> Result<Value, AssemblyError> {
+    match &expression.label {
+        Some(label) => match labels.get(label) {
+            Some(address) => (*address as Value).checked_add(expression.offset)
+                .ok_or_else(|| AssemblyError::OffsetOverflow(line, label.clone(), expression.offset)),
+            None => Err(AssemblyError::UndefinedLabel(line, label.clone()))
+        },
+        None => Ok(expression.offset)
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<Value>, AssemblyError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = 0;
+
+    // First pass: work out where every label points, since statements are
+    // all fixed width we don't need to resolve anything to know that.
+    for (i, raw_line) in source.lines().enumerate() {
+        let line = i + 1;
+        let mut text = raw_line.split(';').next().unwrap_or("").trim();
+
+        while let Some(colon) = text.find(':') {
+            let prefix = text[..colon].trim();
+
+            if let Ok(expected) = prefix.parse::<Address>() {
+                if expected != address {
+                    return Err(AssemblyError::AddressMismatch(line, expected, address));
+                }
+            } else if !is_label(prefix) {
+                return Err(AssemblyError::InvalidLabel(line, prefix.to_string()));
+            } else if labels.insert(prefix.to_string(), address).is_some() {
+                return Err(AssemblyError::DuplicateLabel(line, prefix.to_string()));
+            }
+
+            text = text[colon + 1..].trim();
+        }
+
+        if !text.is_empty() {
+            let statement = parse_statement(line, text)?;
+            address += statement.width();
+            statements.push((line, statement));
+        }
+    }
+
+    // Second pass: encode everything now that labels are known.
+    let mut program = Vec::with_capacity(address);
+    for (line, statement) in statements {
+        match statement {
+            Statement::Instruction { opcode, operands } => {
+                let mut parameters = Vec::with_capacity(operands.len());
+                for operand in &operands {
+                    parameters.push(Parameter {
+                        value: resolve(line, &operand.expression, &labels)?,
+                        mode: operand.mode
+                    });
+                }
+
+                let instruction = Instruction::new(opcode, &parameters);
+                program.push(instruction.encode());
+                program.extend(instruction.parameters().iter().map(|p| p.value));
+            },
+            Statement::Data(values) => {
+                for value in &values {
+                    program.push(resolve(line, value, &labels)?);
+                }
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+// The inverse of assemble, up to labels and comments.
+pub fn to_source(program: &[Value]) -> String {
+    disassembler::listing(&disassembler::disassemble(program))
+}
+
+impl Machine {
+    pub fn from_assembly(source: &str) -> Result<Machine, AssemblyError> {
+        Ok(Machine::from_slots(assemble(source)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::MachineState;
+
+    #[test]
+    fn assemble_modes() -> Result<(), AssemblyError> {
+        assert_eq!(
+            vec![21201, 3, 2, 5, 99],
+            assemble("ADD rb+3, #2, rb+5\nHLT")?
+        );
+        assert_eq!(vec![204, -1], assemble("OUT rb-1")?);
+        assert_eq!(vec![1, 9, 10, 3], assemble("add [9], [10], [3]")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn labels_and_comments() -> Result<(), AssemblyError> {
+        let source = "
+            ; Echo one input back out.
+            start:  IN   [value]
+                    OUT  [value]   ; comments can go anywhere
+                    JF   #0, #end
+            value:  DATA 0, start, value+1
+            end:    HLT
+        ";
+
+        assert_eq!(
+            vec![3, 7, 4, 7, 1106, 0, 10, 0, 0, 8, 99],
+            assemble(source)?
+        );
+
+        let mut machine = Machine::from_assembly(source)?;
+        machine.write(42);
+        machine.run().unwrap();
+        assert_eq!(vec![42], machine.read());
+        assert_eq!(MachineState::Halted, machine.state());
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), AssemblyError> {
+        let quine = vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99];
+        assert_eq!(quine, assemble(&to_source(&quine))?);
+
+        let with_data = vec![1,9,10,3,2,3,11,0,99,30,40,50,11101,0,0,0];
+        assert_eq!(with_data, assemble(&Machine::from_slots(with_data.clone()).listing())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Err(AssemblyError::UnknownMnemonic(1, "JMP".to_string())),
+            assemble("JMP #0")
+        );
+        assert_eq!(
+            Err(AssemblyError::WrongOperandCount(1, "ADD".to_string(), 3, 2)),
+            assemble("ADD #1, #2")
+        );
+        assert_eq!(
+            Err(AssemblyError::InvalidOperand(1, "5".to_string())),
+            assemble("OUT 5")
+        );
+        assert_eq!(
+            Err(AssemblyError::UndefinedLabel(2, "nowhere".to_string())),
+            assemble("HLT\nJT #1, #nowhere")
+        );
+        assert_eq!(
+            Err(AssemblyError::DuplicateLabel(2, "a".to_string())),
+            assemble("a: HLT\na: HLT")
+        );
+        assert_eq!(
+            Err(AssemblyError::AddressMismatch(2, 3, 1)),
+            assemble("0: HLT\n3: HLT")
+        );
+        assert_eq!(
+            Err(AssemblyError::ImmediateModeStorage(1)),
+            assemble("IN #4")
+        );
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(Ok(vec![104, 6, 104, 2, 99]), assemble("OUT #end+2\nOUT #end - 2\nend: HLT"));
+
+        for operand in &["end+3+4", "end+-3", "end-+3", "end+", "end+x", "end+3-4"] {
+            assert_eq!(
+                Err(AssemblyError::InvalidOperand(1, operand.to_string())),
+                assemble(&format!("OUT #{}\nend: HLT", operand))
+            );
+        }
+        assert_eq!(
+            Err(AssemblyError::OffsetOverflow(1, "end".to_string(), Value::MAX)),
+            assemble(&format!("OUT #end+{}\nend: HLT", Value::MAX))
+        );
+    }
+}
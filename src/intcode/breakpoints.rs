@@ -0,0 +1,167 @@
+use std::sync::Arc;
+
+use super::{Address, Value, Machine};
+use super::number::Number;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct BreakpointId(usize);
+
+impl std::fmt::Display for BreakpointId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+pub type Condition<V> = dyn Fn(&Machine<V>) -> bool + Send + Sync;
+
+#[derive(Clone)]
+pub enum Breakpoint<V = Value> {
+    // Stops before the instruction at this address executes.
+    Execute(Address),
+    // These two stop after an instruction that reads or writes this address
+    // as one of its parameters. Fetching the instruction itself isn't a read.
+    Read(Address),
+    Write(Address),
+    // Stops after any instruction that leaves this returning true.
+    Condition(Arc<Condition<V>>)
+}
+
+impl<V> Breakpoint<V> {
+    pub fn condition<F>(predicate: F) -> Self
+    where F: Fn(&Machine<V>) -> bool + Send + Sync + 'static {
+        Breakpoint::Condition(Arc::new(predicate))
+    }
+}
+
+impl<V> std::fmt::Debug for Breakpoint<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Breakpoint::Execute(address) => write!(f, "Execute({})", address),
+            Breakpoint::Read(address) => write!(f, "Read({})", address),
+            Breakpoint::Write(address) => write!(f, "Write({})", address),
+            Breakpoint::Condition(_) => write!(f, "Condition(..)")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Breakpoints<V = Value> {
+    next_id: usize,
+    entries: Vec<(BreakpointId, Breakpoint<V>)>
+}
+
+impl<V: Number> Breakpoints<V> {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn add(&mut self, breakpoint: Breakpoint<V>) -> BreakpointId {
+        let id = BreakpointId(self.next_id);
+        self.next_id += 1;
+        self.entries.push((id, breakpoint));
+        id
+    }
+
+    pub fn remove(&mut self, id: BreakpointId) -> Option<Breakpoint<V>> {
+        let index = self.entries.iter().position(|(i, _)| *i == id)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn get(&self, id: BreakpointId) -> Option<&Breakpoint<V>> {
+        self.entries.iter().find(|(i, _)| *i == id).map(|(_, b)| b)
+    }
+
+    fn find<P>(&self, predicate: P) -> Option<BreakpointId>
+    where P: Fn(&Breakpoint<V>) -> bool {
+        self.entries.iter()
+            .find(|(_, breakpoint)| predicate(breakpoint))
+            .map(|(id, _)| *id)
+    }
+
+    pub(super) fn executing(&self, address: Address) -> Option<BreakpointId> {
+        self.find(|b| matches!(b, Breakpoint::Execute(a) if *a == address))
+    }
+
+    pub(super) fn reading(&self, address: Address) -> Option<BreakpointId> {
+        self.find(|b| matches!(b, Breakpoint::Read(a) if *a == address))
+    }
+
+    pub(super) fn writing(&self, address: Address) -> Option<BreakpointId> {
+        self.find(|b| matches!(b, Breakpoint::Write(a) if *a == address))
+    }
+
+    pub(super) fn condition_met(&self, machine: &Machine<V>) -> Option<BreakpointId> {
+        self.find(|b| match b {
+            Breakpoint::Condition(predicate) => predicate(machine),
+            _ => false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{RunOutcome, OperationalError, MachineState};
+
+    // IN [11], ADD [11], #1, [12], OUT [12], HLT, then data at 11 and 12.
+    fn increment() -> Machine {
+        Machine::from_slots(vec![3, 11, 1001, 11, 1, 12, 4, 12, 99, 0, 0, 0, 0])
+    }
+
+    #[test]
+    fn execute_breakpoint() -> Result<(), OperationalError> {
+        let mut machine = increment();
+        machine.write(1);
+        let id = machine.add_breakpoint(Breakpoint::Execute(6));
+
+        assert_eq!(RunOutcome::Breakpoint(id), machine.run()?);
+        assert_eq!(6, machine.pointer());
+        assert!(machine.pending_output().is_empty());
+
+        // Resuming executes the instruction we stopped at instead of stopping
+        // on it again.
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        assert_eq!(vec![2], machine.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn watchpoints() -> Result<(), OperationalError> {
+        let mut machine = increment();
+        machine.write(1);
+        let write = machine.add_breakpoint(Breakpoint::Write(12));
+        let read = machine.add_breakpoint(Breakpoint::Read(12));
+
+        assert_eq!(RunOutcome::Breakpoint(write), machine.run()?);
+        assert_eq!(6, machine.pointer());
+        assert_eq!(RunOutcome::Breakpoint(read), machine.run()?);
+        assert_eq!(vec![2], machine.read());
+
+        assert!(machine.remove_breakpoint(read).is_some());
+        assert!(machine.remove_breakpoint(read).is_none());
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn condition() -> Result<(), OperationalError> {
+        let mut machine = increment();
+        machine.write(1);
+        let id = machine.add_breakpoint(Breakpoint::condition(|m| !m.pending_output().is_empty()));
+
+        assert_eq!(RunOutcome::Breakpoint(id), machine.run()?);
+        assert_eq!(MachineState::Running, machine.state());
+        assert_eq!(8, machine.pointer());
+
+        machine.clear_breakpoints();
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Address, IntoAddress, Instruction, Machine, Opcode, ParameterMode, Value};
+use super::disassembler::decode_at;
+use super::number::Number;
+
+// How control leaves a basic block.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Exit {
+    Halt,
+    // Runs straight on into the next block, which only starts there because
+    // something else jumps to it.
+    Fallthrough(Address),
+    Jump(Address),
+    Branch { taken: Address, not_taken: Address },
+    // A jump to wherever a parameter in memory says, like the `JT #1, rb+0`
+    // that returns from a function. Conditional ones still have somewhere
+    // static to go when they aren't taken.
+    Dynamic { not_taken: Option<Address> },
+    // Runs into something that doesn't decode to an instruction at this
+    // address, or jumps to a negative address.
+    Invalid(Address)
+}
+
+impl Exit {
+    // Every block this one can go on to that's known without running it.
+    pub fn successors(&self) -> Vec<Address> {
+        match *self {
+            Exit::Fallthrough(next) | Exit::Jump(next) => vec![next],
+            Exit::Branch { taken, not_taken } => vec![taken, not_taken],
+            Exit::Dynamic { not_taken } => not_taken.into_iter().collect(),
+            Exit::Halt | Exit::Invalid(_) => Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock<V = Value> {
+    pub start: Address,
+    pub instructions: Vec<(Address, Instruction<V>)>,
+    pub exit: Exit
+}
+
+// The code reachable from the entry point, found by decoding memory as it
+// is rather than running anything. Code that only gets written while the
+// program runs won't show up, and neither will anything only reachable
+// through a dynamic jump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlFlowGraph<V = Value> {
+    entry: Address,
+    blocks: BTreeMap<Address, BasicBlock<V>>
+}
+
+// Whether a jump's condition is known before running it. Jumps that are
+// never taken are just like any other instruction.
+fn jump_exit<V: Number>(address: Address, instruction: &Instruction<V>) -> Option<Exit> {
+    let parameters = instruction.parameters();
+    let (condition, target) = (&parameters[0], &parameters[1]);
+    let jumps_if = instruction.opcode == Opcode::JumpIfTrue;
+    let not_taken = address + instruction.opcode.parameter_count() + 1;
+
+    let always = match condition.mode {
+        ParameterMode::Immediate if condition.value.is_zero() == jumps_if => return None,
+        ParameterMode::Immediate => true,
+        _ => false
+    };
+
+    Some(match target.mode {
+        ParameterMode::Immediate => match target.value.clone().into_addr() {
+            Ok(taken) if always => Exit::Jump(taken),
+            Ok(taken) => Exit::Branch { taken, not_taken },
+            Err(_) => Exit::Invalid(address)
+        },
+        _ if always => Exit::Dynamic { not_taken: None },
+        _ => Exit::Dynamic { not_taken: Some(not_taken) }
+    })
+}
+
+fn exit_of<V: Number>(address: Address, instruction: &Instruction<V>) -> Option<Exit> {
+    match instruction.opcode {
+        Opcode::Halt => Some(Exit::Halt),
+        Opcode::JumpIfTrue | Opcode::JumpIfFalse => jump_exit(address, instruction),
+        _ => None
+    }
+}
+
+fn width<V>(instruction: &Instruction<V>) -> usize {
+    instruction.opcode.parameter_count() + 1
+}
+
+impl<V: Number> ControlFlowGraph<V> {
+    pub fn build(slots: &[V], entry: Address) -> ControlFlowGraph<V> {
+        ControlFlowGraph::with_entries(slots, entry, &[])
+    }
+
+    // The same, but also starting blocks at, and following code from, other
+    // addresses that are only reached dynamically, like the addresses calls
+    // return to.
+    pub fn with_entries(slots: &[V], entry: Address, others: &[Address]) -> ControlFlowGraph<V> {
+        // First every address a block has to start at: the entry points and
+        // everywhere something can jump or branch to.
+        let mut leaders = BTreeSet::new();
+        let mut unexplored = vec![entry];
+        unexplored.extend_from_slice(others);
+        while let Some(leader) = unexplored.pop() {
+            if !leaders.insert(leader) {
+                continue;
+            }
+
+            let mut address = leader;
+            while let Some(instruction) = decode_at(slots, address) {
+                if let Some(exit) = exit_of(address, &instruction) {
+                    unexplored.extend(exit.successors());
+                    break;
+                }
+                address += width(&instruction);
+            }
+        }
+
+        // Then the blocks themselves, each running up to its exit or the
+        // next leader, whichever comes first.
+        let mut blocks = BTreeMap::new();
+        for &start in &leaders {
+            let mut instructions = Vec::new();
+            let mut address = start;
+            let exit = loop {
+                let instruction = match decode_at(slots, address) {
+                    Some(instruction) => instruction,
+                    None => break Exit::Invalid(address)
+                };
+                let exit = exit_of(address, &instruction);
+                let next = address + width(&instruction);
+                instructions.push((address, instruction));
+
+                match exit {
+                    Some(exit) => break exit,
+                    None if leaders.contains(&next) => break Exit::Fallthrough(next),
+                    None => address = next
+                }
+            };
+            blocks.insert(start, BasicBlock { start, instructions, exit });
+        }
+
+        ControlFlowGraph { entry, blocks }
+    }
+
+    pub fn entry(&self) -> Address {
+        self.entry
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock<V>> {
+        self.blocks.values()
+    }
+
+    pub fn block(&self, start: Address) -> Option<&BasicBlock<V>> {
+        self.blocks.get(&start)
+    }
+
+    // Graphviz, one box per block with its instructions in it. Dynamic
+    // jumps all go to a single `dynamic` node and invalid exits to a node
+    // for the address that wouldn't decode.
+    pub fn to_dot(&self) -> String {
+        let mut dot = vec![
+            "digraph intcode {".to_string(),
+            "    node [shape=box, fontname=\"monospace\"];".to_string()
+        ];
+        let mut dynamic = false;
+
+        for block in self.blocks() {
+            let label: String = block.instructions.iter()
+                .map(|(address, instruction)| format!("{}: {}\\l", address, instruction))
+                .collect();
+            dot.push(format!("    b{} [label=\"{}\"];", block.start, label));
+
+            let from = format!("    b{} ->", block.start);
+            match block.exit {
+                Exit::Halt => {},
+                Exit::Fallthrough(next) | Exit::Jump(next) => {
+                    dot.push(format!("{} b{};", from, next));
+                },
+                Exit::Branch { taken, not_taken } => {
+                    dot.push(format!("{} b{} [label=\"taken\"];", from, taken));
+                    dot.push(format!("{} b{} [label=\"not taken\"];", from, not_taken));
+                },
+                Exit::Dynamic { not_taken } => {
+                    dynamic = true;
+                    dot.push(format!("{} dynamic [style=dashed];", from));
+                    if let Some(not_taken) = not_taken {
+                        dot.push(format!("{} b{} [label=\"not taken\"];", from, not_taken));
+                    }
+                },
+                Exit::Invalid(address) => {
+                    dot.push(format!("    invalid{} [shape=octagon, label=\"invalid {}\"];",
+                                     address, address));
+                    dot.push(format!("{} invalid{};", from, address));
+                }
+            }
+        }
+
+        if dynamic {
+            dot.push("    dynamic [shape=ellipse, style=dashed];".to_string());
+        }
+        dot.push("}".to_string());
+        dot.join("\n")
+    }
+}
+
+impl<V: Number> Machine<V> {
+    // The graph of memory as it is now, starting from address 0.
+    pub fn control_flow_graph(&self) -> ControlFlowGraph<V> {
+        ControlFlowGraph::build(&self.analysed_memory(), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph_of(source: &str) -> ControlFlowGraph {
+        Machine::from_assembly(source).unwrap().control_flow_graph()
+    }
+
+    fn exits(graph: &ControlFlowGraph) -> Vec<(Address, Exit)> {
+        graph.blocks().map(|block| (block.start, block.exit)).collect()
+    }
+
+    #[test]
+    fn branches() {
+        let graph = graph_of("
+                    IN   [x]
+                    JT   [x], #yes
+                    OUT  #0
+                    HLT
+            yes:    OUT  #1
+                    JF   #0, #end
+                    DATA 7
+            end:    HLT
+            x:      DATA 0
+        ");
+
+        assert_eq!(vec![
+            (0, Exit::Branch { taken: 8, not_taken: 5 }),
+            (5, Exit::Halt),
+            (8, Exit::Jump(14)),
+            (14, Exit::Halt)
+        ], exits(&graph));
+        assert_eq!(2, graph.block(0).unwrap().instructions.len());
+    }
+
+    #[test]
+    fn loops_split_blocks() {
+        let graph = graph_of("
+                    IN   [x]
+                    JT   #0, #nowhere
+            loop:   ADD  [x], #-1, [x]
+                    JT   [x], #loop
+                    HLT
+            x:      DATA 0
+            nowhere: HLT
+        ");
+
+        assert_eq!(vec![
+            (0, Exit::Fallthrough(5)),
+            (5, Exit::Branch { taken: 5, not_taken: 12 }),
+            (12, Exit::Halt)
+        ], exits(&graph));
+    }
+
+    #[test]
+    fn dynamic_jumps() {
+        // The return at the end of func is the same `2105,1,0` as in day 9.
+        let graph = graph_of("
+                    RBO  #100
+                    ADD  #ret, #0, rb+0
+                    JT   #1, #func
+            ret:    OUT  [value]
+                    HLT
+            func:   ADD  [value], #1, [value]
+                    JT   #1, rb+0
+            value:  DATA 0
+        ");
+
+        assert_eq!(vec![(0, Exit::Jump(12)), (12, Exit::Dynamic { not_taken: None })],
+                   exits(&graph));
+        assert_eq!(vec![2105, 1, 0], Machine::from_assembly("JT #1, rb+0").unwrap().memory().to_vec());
+
+        let graph = graph_of("JF [3], rb+1\nDATA 0");
+        assert_eq!(vec![(0, Exit::Dynamic { not_taken: Some(3) }), (3, Exit::Invalid(3))],
+                   exits(&graph));
+    }
+
+    #[test]
+    fn dot() {
+        let graph = graph_of("
+            loop:   IN   [x]
+                    JT   [x], #loop
+                    JT   #1, rb+0
+            x:      DATA 0
+        ");
+
+        assert_eq!("\
+digraph intcode {
+    node [shape=box, fontname=\"monospace\"];
+    b0 [label=\"0: IN   [8]\\l2: JT   [8], #0\\l\"];
+    b0 -> b0 [label=\"taken\"];
+    b0 -> b5 [label=\"not taken\"];
+    b5 [label=\"5: JT   #1, rb+0\\l\"];
+    b5 -> dynamic [style=dashed];
+    dynamic [shape=ellipse, style=dashed];
+}", graph.to_dot());
+    }
+}
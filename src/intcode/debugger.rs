@@ -0,0 +1,465 @@
+use thiserror::Error;
+
+use super::{Address, Value, Machine, MachineState, Opcode, OperationalError, RunOutcome};
+use super::breakpoints::{self, BreakpointId};
+use super::disassembler::{self, Line};
+
+// The command handling for the debugger binary lives here so that it can be
+// driven (and tested) without a terminal attached.
+
+const HELP: &str = "\
+step [n]              (s) execute n instructions, default 1
+continue              (c) run until a breakpoint, halt or input block
+back [n]              (bk) undo n instructions, default 1
+rewind <count>        undo until n instructions have been executed
+break <where>         (b) break at an address, on an opcode like OUT, or
+                          after a read (r12) or write (w12) of an address
+delete <where>        (d) remove a breakpoint
+breakpoints           (bl) list breakpoints
+list [addr] [n]       (l) disassemble n instructions, default from pc
+examine <addr> [n]    (x) show n memory words
+set <addr> <value>    change a memory word
+rb [value]            show or change the relative base
+input <values...>     (i) feed input values
+output                (o) show and consume pending output
+info                  show pc, relative base, state and counters
+help                  (h) show this message
+quit                  (q) exit";
+
+// How many instructions back the debugger can step unless told otherwise.
+// Each one takes an undo entry, so without a limit a long interactive run
+// like day 13 or 25 keeps eating memory.
+pub const DEFAULT_HISTORY: usize = 1_000_000;
+
+// The most words `examine` shows at once, more than fits on any screen.
+const EXAMINE_LIMIT: usize = 10_000;
+
+#[derive(Debug, Error)]
+pub enum DebuggerError {
+    #[error("`{0}` is not a known command, try `help`.")]
+    UnknownCommand(String),
+    #[error("`{0}` needs a {1}.")]
+    MissingArgument(String, &'static str),
+    #[error("`{0}` is not a number.")]
+    InvalidNumber(String),
+    #[error("`{0}` is not an address, opcode mnemonic or watched address.")]
+    InvalidBreakpoint(String),
+    #[error("There is no breakpoint at `{0}`.")]
+    NoSuchBreakpoint(String),
+    #[error("Can only examine up to {} words at once, not {0}.", EXAMINE_LIMIT)]
+    TooManyWords(usize),
+    #[error("{0} words from address {1} goes past the biggest address.")]
+    PastLastAddress(usize, Address),
+    #[error("Machine error: {0}")]
+    MachineError(#[from] OperationalError)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    Address(Address),
+    Opcode(Opcode),
+    Read(Address),
+    Write(Address)
+}
+
+impl Breakpoint {
+    fn parse(token: &str) -> Result<Self, DebuggerError> {
+        let invalid = || DebuggerError::InvalidBreakpoint(token.to_string());
+
+        if let Ok(address) = token.parse::<Address>() {
+            Ok(Breakpoint::Address(address))
+        } else if let Some(address) = token.strip_prefix('r') {
+            address.parse().map(Breakpoint::Read).map_err(|_| invalid())
+        } else if let Some(address) = token.strip_prefix('w') {
+            address.parse().map(Breakpoint::Write).map_err(|_| invalid())
+        } else {
+            Opcode::from_mnemonic(token)
+                .map(Breakpoint::Opcode)
+                .ok_or_else(invalid)
+        }
+    }
+
+    fn to_machine_breakpoint(self) -> breakpoints::Breakpoint {
+        match self {
+            Breakpoint::Address(address) => breakpoints::Breakpoint::Execute(address),
+            Breakpoint::Read(address) => breakpoints::Breakpoint::Read(address),
+            Breakpoint::Write(address) => breakpoints::Breakpoint::Write(address),
+            Breakpoint::Opcode(opcode) => breakpoints::Breakpoint::condition(move |machine| {
+                machine.current_instruction().map(|i| i.opcode) == Some(opcode)
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Breakpoint::Address(address) => write!(f, "address {}", address),
+            Breakpoint::Opcode(opcode) => write!(f, "opcode {}", opcode),
+            Breakpoint::Read(address) => write!(f, "read of {}", address),
+            Breakpoint::Write(address) => write!(f, "write to {}", address)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Debugger {
+    machine: Machine,
+    // The machine keeps the actual breakpoints, this just remembers what
+    // each one was asked for as.
+    breakpoints: Vec<(Breakpoint, BreakpointId)>
+}
+
+fn parse_number<T: std::str::FromStr>(token: &str) -> Result<T, DebuggerError> {
+    token.parse::<T>().map_err(|_| DebuggerError::InvalidNumber(token.to_string()))
+}
+
+fn required<'a>(command: &str,
+                arg: Option<&&'a str>,
+                what: &'static str) -> Result<&'a str, DebuggerError> {
+    arg.copied().ok_or_else(|| DebuggerError::MissingArgument(command.to_string(), what))
+}
+
+impl Debugger {
+    // Records the machine's history from here on, so it can be stepped back
+    // through, as far back as DEFAULT_HISTORY instructions.
+    pub fn new(machine: Machine) -> Self {
+        Debugger::with_history(machine, Some(DEFAULT_HISTORY))
+    }
+
+    // The same, but going back as far as history instructions, or all the
+    // way if it's None.
+    pub fn with_history(mut machine: Machine, history: Option<usize>) -> Self {
+        machine.start_recording(history);
+        Debugger {
+            machine,
+            breakpoints: Vec::new()
+        }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    // The decoded instruction at the pointer, or a DATA line if the pointer
+    // is at something that won't execute.
+    pub fn current_line(&self) -> String {
+        let address = self.machine.pointer();
+        match self.machine.current_instruction() {
+            Some(instruction) => Line::Instruction { address, instruction },
+            None => Line::Data {
+                address,
+                value: self.machine.memory().get(address).copied().unwrap_or(0)
+            }
+        }.to_string()
+    }
+
+    fn stop_description(&self, breakpoint: Option<Breakpoint>) -> String {
+        let reason = match (breakpoint, self.machine.state()) {
+            (_, MachineState::Halted) => "Halted.".to_string(),
+            (_, MachineState::Blocked) => "Blocked waiting for input.".to_string(),
+            (Some(breakpoint), _) => format!("Breakpoint at {}.", breakpoint),
+            (None, MachineState::Running) => "Stepped.".to_string()
+        };
+        format!("{}\n{}", reason, self.current_line())
+    }
+
+    fn step(&mut self, count: usize) -> Result<String, DebuggerError> {
+        for _ in 0..count {
+            self.machine.step()?;
+            if self.machine.state() != MachineState::Running {
+                break;
+            }
+        }
+        Ok(self.stop_description(None))
+    }
+
+    fn step_back(&mut self, count: usize) -> String {
+        let stepped = (0..count).take_while(|_| self.machine.step_back()).count();
+        let reason = if stepped < count {
+            format!("Stepped back {}, history only goes back to instruction {}.",
+                    stepped, self.machine.instruction_counter())
+        } else {
+            format!("Stepped back {}.", stepped)
+        };
+        format!("{}\n{}", reason, self.current_line())
+    }
+
+    fn rewind(&mut self, count: usize) -> String {
+        let reason = if self.machine.rewind_to(count) {
+            format!("Rewound to instruction {}.", count)
+        } else {
+            format!("Couldn't rewind to instruction {}, stopped at {}.",
+                    count, self.machine.instruction_counter())
+        };
+        format!("{}\n{}", reason, self.current_line())
+    }
+
+    fn continue_execution(&mut self) -> Result<String, DebuggerError> {
+        let breakpoint = match self.machine.run()? {
+            RunOutcome::Breakpoint(id) => self.breakpoints.iter()
+                .find(|(_, i)| *i == id)
+                .map(|(breakpoint, _)| *breakpoint),
+            _ => None
+        };
+        Ok(self.stop_description(breakpoint))
+    }
+
+    fn list(&self, start: Address, count: usize) -> String {
+        self.machine.disassemble_from(start)
+            .iter()
+            .take(count)
+            .map(|line| {
+                let marker = if line.address() == self.machine.pointer() { "=>" } else { "  " };
+                format!("{}{}", marker, line)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn examine(&self, start: Address, count: usize) -> Result<String, DebuggerError> {
+        if count > EXAMINE_LIMIT {
+            return Err(DebuggerError::TooManyWords(count));
+        }
+        let end = start.checked_add(count).ok_or(DebuggerError::PastLastAddress(count, start))?;
+
+        Ok((start..end)
+            .map(|address| {
+                let value = self.machine.memory().get(address).copied().unwrap_or(0);
+                format!("{:>5}: {}", address, value)
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    fn info(&self) -> String {
+        format!("pc: {}\nrb: {}\nstate: {}\ninstructions: {}\npending input: {:?}\npending output: {:?}",
+                self.machine.pointer(),
+                self.machine.relative_base(),
+                self.machine.state(),
+                self.machine.instruction_counter(),
+                self.machine.pending_input(),
+                self.machine.pending_output())
+    }
+
+    pub fn execute(&mut self, line: &str) -> Result<String, DebuggerError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = match words.first() {
+            Some(command) => *command,
+            None => return Ok(String::new())
+        };
+        let args = &words[1..];
+
+        match command {
+            "step" | "s" => {
+                let count = match args.first() {
+                    Some(n) => parse_number(n)?,
+                    None => 1
+                };
+                self.step(count)
+            },
+            "continue" | "c" => self.continue_execution(),
+            "back" | "bk" => {
+                let count = match args.first() {
+                    Some(n) => parse_number(n)?,
+                    None => 1
+                };
+                Ok(self.step_back(count))
+            },
+            "rewind" => {
+                let count = parse_number(required(command, args.first(), "instruction count")?)?;
+                Ok(self.rewind(count))
+            },
+            "break" | "b" => {
+                let breakpoint = Breakpoint::parse(required(command, args.first(), "breakpoint")?)?;
+                if !self.breakpoints.iter().any(|(b, _)| *b == breakpoint) {
+                    let id = self.machine.add_breakpoint(breakpoint.to_machine_breakpoint());
+                    self.breakpoints.push((breakpoint, id));
+                }
+                Ok(format!("Breakpoint set at {}.", breakpoint))
+            },
+            "delete" | "d" => {
+                let token = required(command, args.first(), "breakpoint")?;
+                let breakpoint = Breakpoint::parse(token)?;
+                match self.breakpoints.iter().position(|(b, _)| *b == breakpoint) {
+                    Some(index) => {
+                        let (_, id) = self.breakpoints.remove(index);
+                        self.machine.remove_breakpoint(id);
+                        Ok(format!("Breakpoint at {} deleted.", breakpoint))
+                    },
+                    None => Err(DebuggerError::NoSuchBreakpoint(token.to_string()))
+                }
+            },
+            "breakpoints" | "bl" => {
+                if self.breakpoints.is_empty() {
+                    Ok("No breakpoints.".to_string())
+                } else {
+                    Ok(self.breakpoints.iter()
+                        .map(|(breakpoint, _)| breakpoint.to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n"))
+                }
+            },
+            "list" | "l" => {
+                let start = match args.first() {
+                    Some(addr) => parse_number(addr)?,
+                    None => self.machine.pointer()
+                };
+                let count = match args.get(1) {
+                    Some(n) => parse_number(n)?,
+                    None => 10
+                };
+                Ok(self.list(start, count))
+            },
+            "examine" | "x" => {
+                let start = parse_number(required(command, args.first(), "address")?)?;
+                let count = match args.get(1) {
+                    Some(n) => parse_number(n)?,
+                    None => 1
+                };
+                self.examine(start, count)
+            },
+            "set" => {
+                let address: Address = parse_number(required(command, args.first(), "address")?)?;
+                let value: Value = parse_number(required(command, args.get(1), "value")?)?;
+                self.machine.set(address, value)?;
+                self.examine(address, 1)
+            },
+            "rb" => {
+                if let Some(value) = args.first() {
+                    self.machine.set_relative_base(parse_number(value)?);
+                }
+                Ok(format!("rb: {}", self.machine.relative_base()))
+            },
+            "input" | "i" => {
+                required(command, args.first(), "value")?;
+                for value in args {
+                    self.machine.write(parse_number(value)?);
+                }
+                Ok(format!("pending input: {:?}", self.machine.pending_input()))
+            },
+            "output" | "o" => Ok(format!("{:?}", self.machine.read())),
+            "info" => Ok(self.info()),
+            "help" | "h" => Ok(HELP.to_string()),
+            _ => Err(DebuggerError::UnknownCommand(command.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo_twice() -> Debugger {
+        // IN [9], OUT [9], IN [9], OUT [9], HLT
+        Debugger::new(Machine::from_slots(vec![3, 9, 4, 9, 3, 9, 4, 9, 99, 0]))
+    }
+
+    #[test]
+    fn step_and_continue() -> Result<(), DebuggerError> {
+        let mut debugger = echo_twice();
+
+        assert_eq!("Blocked waiting for input.\n    0: IN   [9]", debugger.execute("s")?);
+        debugger.execute("input 7 8")?;
+        assert_eq!("Stepped.\n    2: OUT  [9]", debugger.execute("step")?);
+        // Halting still advances the pointer past the HLT.
+        assert_eq!("Halted.\n    9: DATA 8", debugger.execute("c")?);
+        assert_eq!("[7, 8]", debugger.execute("output")?);
+        assert_eq!("[]", debugger.execute("o")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn breakpoints() -> Result<(), DebuggerError> {
+        let mut debugger = echo_twice();
+        debugger.execute("i 1 2")?;
+        debugger.execute("b 4")?;
+        debugger.execute("b OUT")?;
+
+        assert_eq!("Breakpoint at opcode OUT.\n    2: OUT  [9]", debugger.execute("c")?);
+        assert_eq!("Breakpoint at address 4.\n    4: IN   [9]", debugger.execute("c")?);
+        debugger.execute("d out")?;
+        assert_eq!("address 4", debugger.execute("bl")?);
+        assert_eq!("Halted.\n    9: DATA 2", debugger.execute("c")?);
+        assert_eq!(MachineState::Halted, debugger.machine().state());
+
+        assert!(debugger.execute("d 12").is_err());
+        assert!(debugger.execute("b JMP").is_err());
+        assert!(debugger.execute("b rx").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn watchpoints() -> Result<(), DebuggerError> {
+        let mut debugger = echo_twice();
+        debugger.execute("i 1 2")?;
+        debugger.execute("b w9")?;
+        debugger.execute("b r9")?;
+
+        assert_eq!("Breakpoint at write to 9.\n    2: OUT  [9]", debugger.execute("c")?);
+        assert_eq!("Breakpoint at read of 9.\n    4: IN   [9]", debugger.execute("c")?);
+        assert_eq!("[1]", debugger.execute("o")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn going_backwards() -> Result<(), DebuggerError> {
+        let mut debugger = echo_twice();
+        debugger.execute("i 1 2")?;
+        debugger.execute("c")?;
+        assert_eq!("[1, 2]", debugger.execute("o")?);
+
+        assert_eq!("Stepped back 2.\n    6: OUT  [9]", debugger.execute("bk 2")?);
+        assert_eq!("[]", debugger.execute("o")?);
+        assert_eq!("    9: 2", debugger.execute("x 9")?);
+        assert_eq!("Rewound to instruction 2.\n    4: IN   [9]", debugger.execute("rewind 2")?);
+        assert_eq!("    9: 1", debugger.execute("x 9")?);
+        assert_eq!("Stepped back 2, history only goes back to instruction 0.\n    0: IN   [9]",
+                   debugger.execute("back 5")?);
+        assert!(debugger.execute("rewind").is_err());
+
+        assert_eq!("Halted.\n    9: DATA 2", debugger.execute("c")?);
+        assert_eq!("[1, 2]", debugger.execute("o")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn limited_history() -> Result<(), DebuggerError> {
+        let machine = Machine::from_slots(vec![3, 9, 4, 9, 3, 9, 4, 9, 99, 0]);
+        let mut debugger = Debugger::with_history(machine, Some(2));
+        debugger.execute("i 1 2")?;
+        debugger.execute("c")?;
+
+        assert_eq!("Stepped back 2, history only goes back to instruction 3.\n    6: OUT  [9]",
+                   debugger.execute("back 5")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn memory_and_relative_base() -> Result<(), DebuggerError> {
+        let mut debugger = echo_twice();
+
+        assert_eq!("    9: 5", debugger.execute("set 9 5")?);
+        assert_eq!("    8: 99\n    9: 5\n   10: 0", debugger.execute("x 8 3")?);
+        assert!(matches!(debugger.execute(&format!("x {} 2", Address::MAX)),
+                         Err(DebuggerError::PastLastAddress(2, Address::MAX))));
+        assert!(matches!(debugger.execute("x 0 1000000000"),
+                         Err(DebuggerError::TooManyWords(1000000000))));
+        assert_eq!("rb: -4", debugger.execute("rb -4")?);
+        assert_eq!(-4, debugger.machine().relative_base());
+        assert_eq!(
+            "=>    0: IN   [9]\n      2: OUT  [9]",
+            debugger.execute("list 0 2")?
+        );
+
+        assert!(debugger.execute("frobnicate").is_err());
+        assert!(debugger.execute("set 1").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1064 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::{Address, IntoAddress, Instruction, Machine, Opcode, Parameter, ParameterMode, Value};
+use super::control_flow::{BasicBlock, ControlFlowGraph, Exit};
+use super::number::{Number, Overflow};
+
+// Turns a program into C-like pseudocode, for working out what puzzle
+// programs do without poking them with inputs until it becomes clear.
+//
+// Functions are found from the calling convention the puzzle programs use:
+// the caller puts its arguments at rb+1 onwards and the address to return to
+// at rb+0, then jumps. The function moves the relative base up past its
+// frame with a RBO, moves it back down before returning, and returns with a
+// jump to rb+0. Following the relative base through each function lets
+// stack slots get names instead of being rb+whatever.
+//
+// Control flow is turned back into ifs and loops where it's structured
+// enough for that, and gotos where it isn't. Like ControlFlowGraph, this
+// only sees code that's in memory before the program runs.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    AtLeast,
+    Equal,
+    NotEqual
+}
+
+impl Comparison {
+    fn negate(self) -> Comparison {
+        match self {
+            Comparison::Less => Comparison::AtLeast,
+            Comparison::AtLeast => Comparison::Less,
+            Comparison::Equal => Comparison::NotEqual,
+            Comparison::NotEqual => Comparison::Equal
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::AtLeast => ">=",
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!="
+        }
+    }
+}
+
+// Somewhere a parameter reads or writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Place<V> {
+    Memory(V),
+    // A slot in the current function's frame, counted from where the
+    // relative base was when it was called. Main's frame starts at zero, so
+    // its slots are just memory.
+    Slot(Value),
+    // Relative to a base we lost track of.
+    Relative(V)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr<V> {
+    Const(V),
+    Load(Place<V>),
+    Add(Box<Expr<V>>, Box<Expr<V>>),
+    Multiply(Box<Expr<V>>, Box<Expr<V>>),
+    Compare(Comparison, Box<Expr<V>>, Box<Expr<V>>)
+}
+
+impl<V: Number> Expr<V> {
+    // Jumps test for anything that isn't zero, so anything that isn't
+    // already a comparison gets compared to zero.
+    fn truthy(self) -> Expr<V> {
+        match self {
+            Expr::Compare(..) => self,
+            other => Expr::Compare(Comparison::NotEqual, Box::new(other),
+                                   Box::new(Expr::Const(V::default())))
+        }
+    }
+
+    fn negate(self) -> Expr<V> {
+        match self.truthy() {
+            Expr::Compare(comparison, a, b) => Expr::Compare(comparison.negate(), a, b),
+            _ => unreachable!("truthy always makes a comparison")
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Stmt<V> {
+    Assign(Place<V>, Expr<V>),
+    Input(Place<V>),
+    Output(Expr<V>),
+    // A RBO we couldn't follow, after which slots are relative again.
+    MoveBase(Expr<V>),
+    Call(Address, Vec<Place<V>>),
+    Return,
+    // A jump to an address computed at runtime that isn't a return.
+    JumpTo(Expr<V>),
+    Halt,
+    Invalid(Address),
+    If(Expr<V>, Vec<Stmt<V>>, Vec<Stmt<V>>),
+    Loop(Vec<Stmt<V>>),
+    While(Expr<V>, Vec<Stmt<V>>),
+    DoWhile(Vec<Stmt<V>>, Expr<V>),
+    Break,
+    Continue,
+    Goto(Address),
+    // Every block starts with one, but only the ones something goes to
+    // get shown.
+    Label(Address)
+}
+
+// How a block hands over to the next one, once calls are taken out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum End<V> {
+    // Leaves the function, or the program.
+    Stop(Stmt<V>),
+    Next(Address),
+    Branch(Expr<V>, Address, Address),
+    // A conditional return or dynamic jump, which goes on to the next
+    // block when it isn't taken.
+    Escape(Expr<V>, Stmt<V>, Address)
+}
+
+impl<V> End<V> {
+    fn successors(&self) -> Vec<Address> {
+        match *self {
+            End::Stop(_) => Vec::new(),
+            End::Next(next) | End::Escape(_, _, next) => vec![next],
+            End::Branch(_, taken, not_taken) => vec![taken, not_taken]
+        }
+    }
+
+    fn leaves(&self) -> bool {
+        matches!(self, End::Stop(_) | End::Escape(..))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block<V> {
+    statements: Vec<Stmt<V>>,
+    end: End<V>
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Call {
+    target: Address,
+    // Where the call returns to, and the instruction storing that at rb+0.
+    returns_to: Address,
+    store: Address,
+    arguments: usize
+}
+
+// Which rb+n an instruction writes to, if it writes to the stack at all.
+fn stack_store<V: Number>(instruction: &Instruction<V>) -> Option<Value> {
+    let destination = instruction.parameters().last()?;
+    if instruction.opcode.writes_last_parameter() && destination.mode == ParameterMode::Relative {
+        destination.value.to_value()
+    } else {
+        None
+    }
+}
+
+// The address an ADD or MUL of two immediates works out to.
+pub(super) fn constant_address<V: Number>(instruction: &Instruction<V>) -> Option<Address> {
+    let parameters = instruction.parameters();
+    if parameters.len() < 2 || parameters[..2].iter().any(|p| p.mode != ParameterMode::Immediate) {
+        return None;
+    }
+
+    let (a, b) = (&parameters[0].value, &parameters[1].value);
+    let value = match instruction.opcode {
+        Opcode::Add => a.add_with(b, Overflow::Checked)?,
+        Opcode::Multiply => a.mul_with(b, Overflow::Checked)?,
+        _ => return None
+    };
+    value.into_addr().ok()
+}
+
+// A call is an unconditional jump straight after storing the address of the
+// next instruction at rb+0. The stores just before that to rb+1, rb+2 and so
+// on are its arguments.
+fn find_call<V: Number>(block: &BasicBlock<V>) -> Option<Call> {
+    let target = match block.exit {
+        Exit::Jump(target) => target,
+        _ => return None
+    };
+    let ((jump, _), stores) = block.instructions.split_last()?;
+    let returns_to = jump + 3;
+
+    let mut store = None;
+    let mut offsets = HashSet::new();
+    for (address, instruction) in stores.iter().rev() {
+        let offset = match stack_store(instruction) {
+            Some(offset) => offset,
+            None => break
+        };
+        if offset == 0 && store.is_none() && constant_address(instruction) == Some(returns_to) {
+            store = Some(*address);
+        }
+        offsets.insert(offset);
+    }
+
+    let arguments = (1..).take_while(|offset| offsets.contains(offset)).count();
+    Some(Call { target, returns_to, store: store?, arguments })
+}
+
+// The addresses calls return to are only ever jumped to dynamically, so they
+// aren't in the graph until we know where the calls are. Finding them can
+// turn up more calls, so this goes round until it stops finding any.
+fn graph_with_calls<V: Number>(slots: &[V]) -> (ControlFlowGraph<V>, BTreeMap<Address, Call>) {
+    let mut returns = BTreeSet::new();
+    loop {
+        let others: Vec<Address> = returns.iter().copied().collect();
+        let graph = ControlFlowGraph::with_entries(slots, 0, &others);
+        let calls: BTreeMap<Address, Call> = graph.blocks()
+            .filter_map(|block| Some((block.start, find_call(block)?)))
+            .collect();
+
+        let before = returns.len();
+        returns.extend(calls.values().map(|call| call.returns_to));
+        if returns.len() == before {
+            return (graph, calls);
+        }
+    }
+}
+
+// How many times each address is read positionally anywhere in the program.
+fn positional_reads<V: Number>(graph: &ControlFlowGraph<V>) -> HashMap<V, usize> {
+    let mut reads = HashMap::new();
+    for block in graph.blocks() {
+        for (_, instruction) in &block.instructions {
+            let parameters = instruction.parameters();
+            let read = if instruction.opcode.writes_last_parameter() {
+                &parameters[..parameters.len() - 1]
+            } else {
+                parameters
+            };
+            for parameter in read.iter().filter(|p| p.mode == ParameterMode::Positional) {
+                *reads.entry(parameter.value.clone()).or_insert(0) += 1;
+            }
+        }
+    }
+    reads
+}
+
+// Cooper, Harvey and Kennedy's "A Simple, Fast Dominance Algorithm", over
+// nodes numbered by index. None for the root and anything it can't reach.
+fn immediate_dominators(root: usize, successors: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let count = successors.len();
+
+    let mut order = Vec::new();
+    let mut seen = vec![false; count];
+    let mut stack = vec![(root, 0)];
+    seen[root] = true;
+    while let Some((node, next)) = stack.pop() {
+        match successors[node].get(next) {
+            Some(&successor) => {
+                stack.push((node, next + 1));
+                if !seen[successor] {
+                    seen[successor] = true;
+                    stack.push((successor, 0));
+                }
+            },
+            None => order.push(node)
+        }
+    }
+    order.reverse();
+
+    let mut rank = vec![usize::MAX; count];
+    for (i, &node) in order.iter().enumerate() {
+        rank[node] = i;
+    }
+    let mut predecessors = vec![Vec::new(); count];
+    for (node, targets) in successors.iter().enumerate() {
+        for &target in targets {
+            predecessors[target].push(node);
+        }
+    }
+
+    let mut idoms = vec![None; count];
+    idoms[root] = Some(root);
+    let intersect = |idoms: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while rank[a] > rank[b] {
+                a = idoms[a].unwrap();
+            }
+            while rank[b] > rank[a] {
+                b = idoms[b].unwrap();
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in &order[1..] {
+            let mut idom = None;
+            for &predecessor in &predecessors[node] {
+                if idoms[predecessor].is_some() {
+                    idom = Some(match idom {
+                        None => predecessor,
+                        Some(other) => intersect(&idoms, predecessor, other)
+                    });
+                }
+            }
+            if idom != idoms[node] {
+                idoms[node] = idom;
+                changed = true;
+            }
+        }
+    }
+
+    idoms[root] = None;
+    idoms
+}
+
+fn dominates(idoms: &[Option<usize>], a: usize, mut b: usize) -> bool {
+    loop {
+        if a == b {
+            return true;
+        }
+        match idoms[b] {
+            Some(idom) => b = idom,
+            None => return false
+        }
+    }
+}
+
+// Whether there's a continue for this loop in statements, rather than for
+// one nested inside it.
+fn continues<V>(statements: &[Stmt<V>]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Stmt::Continue => true,
+        Stmt::If(_, then, otherwise) => continues(then) || continues(otherwise),
+        _ => false
+    })
+}
+
+fn is_only<V>(statements: &[Stmt<V>], jump: &Stmt<V>) -> bool
+where V: PartialEq {
+    statements.len() == 1 && &statements[0] == jump
+}
+
+// Tidies the body of an endless loop into a while or do while if it has an
+// exit in the right place.
+fn structure_loop<V: Number>(mut body: Vec<Stmt<V>>) -> Stmt<V> {
+    if body.last() == Some(&Stmt::Continue) {
+        body.pop();
+    }
+
+    // do { ... if (c) continue; break; }
+    if body.len() >= 2 && body.last() == Some(&Stmt::Break) {
+        if let Stmt::If(_, then, otherwise) = &body[body.len() - 2] {
+            if is_only(then, &Stmt::Continue) && otherwise.is_empty() &&
+               !continues(&body[..body.len() - 2]) {
+                body.pop();
+                if let Some(Stmt::If(condition, _, _)) = body.pop() {
+                    return Stmt::DoWhile(body, condition);
+                }
+            }
+        }
+    }
+
+    // do { ... if (c) break; }
+    if let Some(Stmt::If(_, then, otherwise)) = body.last() {
+        if is_only(then, &Stmt::Break) && otherwise.is_empty() && !continues(&body[..body.len() - 1]) {
+            if let Some(Stmt::If(condition, _, _)) = body.pop() {
+                return Stmt::DoWhile(body, condition.negate());
+            }
+        }
+    }
+
+    // while (c) { ... }
+    if let Some(Stmt::If(_, then, otherwise)) = body.first() {
+        if is_only(then, &Stmt::Break) && otherwise.is_empty() {
+            if let Stmt::If(condition, _, _) = body.remove(0) {
+                return Stmt::While(condition.negate(), body);
+            }
+        }
+    }
+
+    Stmt::Loop(body)
+}
+
+// The offset of the relative base after a RBO by change. Only immediate
+// changes can be followed.
+fn moved<V: Number>(offset: Option<Value>, change: &Parameter<V>) -> Option<Value> {
+    match change.mode {
+        ParameterMode::Immediate => offset.zip(change.value.to_value())
+            .and_then(|(offset, change)| offset.checked_add(change)),
+        _ => None
+    }
+}
+
+fn arithmetic<V: Number>(opcode: Opcode, a: Expr<V>, b: Expr<V>) -> Expr<V> {
+    let (identity, folded) = match (opcode, &a, &b) {
+        (Opcode::Add, Expr::Const(x), Expr::Const(y)) => (0, x.add_with(y, Overflow::Checked)),
+        (Opcode::Multiply, Expr::Const(x), Expr::Const(y)) => (1, x.mul_with(y, Overflow::Checked)),
+        (Opcode::Add, _, _) => (0, None),
+        _ => (1, None)
+    };
+    if let Some(value) = folded {
+        return Expr::Const(value);
+    }
+
+    let identity = V::from_value(identity);
+    match (a, b) {
+        (Expr::Const(x), other) | (other, Expr::Const(x)) if x == identity => other,
+        (a, b) if opcode == Opcode::Add => Expr::Add(Box::new(a), Box::new(b)),
+        (a, b) => Expr::Multiply(Box::new(a), Box::new(b))
+    }
+}
+
+// Turns one function's blocks into statements.
+struct Translator<'a, V> {
+    main: bool,
+    reads: &'a HashMap<V, usize>
+}
+
+impl<'a, V: Number> Translator<'a, V> {
+    fn place(&self, parameter: &Parameter<V>, offset: Option<Value>) -> Place<V> {
+        match parameter.mode {
+            ParameterMode::Relative => {
+                let slot = offset.zip(parameter.value.to_value())
+                    .and_then(|(offset, relative)| offset.checked_add(relative));
+                match slot {
+                    Some(slot) if self.main => Place::Memory(V::from_value(slot)),
+                    Some(slot) => Place::Slot(slot),
+                    None => Place::Relative(parameter.value.clone())
+                }
+            },
+            _ => Place::Memory(parameter.value.clone())
+        }
+    }
+
+    fn operand(&self, parameter: &Parameter<V>, offset: Option<Value>) -> Expr<V> {
+        match parameter.mode {
+            ParameterMode::Immediate => Expr::Const(parameter.value.clone()),
+            _ => Expr::Load(self.place(parameter, offset))
+        }
+    }
+
+    // Comparisons into a scratch address that only the jump straight after
+    // reads go into the jump's condition instead of memory.
+    fn inlines(&self, destination: &Parameter<V>, jump: &Instruction<V>) -> bool {
+        destination.mode == ParameterMode::Positional &&
+            jump.parameters()[0] == *destination &&
+            self.reads.get(&destination.value) == Some(&1)
+    }
+
+    fn condition(&self, jump: &Instruction<V>, offset: Option<Value>,
+                 inlined: Option<Expr<V>>) -> Expr<V> {
+        let value = inlined.unwrap_or_else(|| self.operand(&jump.parameters()[0], offset)).truthy();
+        if jump.opcode == Opcode::JumpIfTrue { value } else { value.negate() }
+    }
+
+    // Where the relative base is after block, relative to where it was when
+    // the function was called, if we can still tell.
+    fn offset_after(&self, block: &BasicBlock<V>, offset: Option<Value>) -> Option<Value> {
+        block.instructions.iter()
+            .filter(|(_, instruction)| instruction.opcode == Opcode::RelativeBaseOffset)
+            .fold(offset, |offset, (_, instruction)| moved(offset, &instruction.parameters()[0]))
+    }
+
+    fn translate(&self, block: &BasicBlock<V>, mut offset: Option<Value>, call: Option<&Call>)
+                 -> Block<V> {
+        // The jump ending the block is the end rather than a statement.
+        let jumps = match block.exit {
+            Exit::Jump(_) | Exit::Branch { .. } | Exit::Dynamic { .. } => true,
+            Exit::Invalid(address) => block.instructions.last().map(|(a, _)| *a) == Some(address),
+            Exit::Halt | Exit::Fallthrough(_) => false
+        };
+        let body = if jumps { block.instructions.len() - 1 } else { block.instructions.len() };
+        let jump = if jumps { block.instructions.last().map(|(_, i)| i) } else { None };
+
+        let mut statements = Vec::new();
+        let mut inlined = None;
+        for (i, (address, instruction)) in block.instructions[..body].iter().enumerate() {
+            if call.map(|call| call.store) == Some(*address) {
+                continue;
+            }
+
+            let parameters = instruction.parameters();
+            match instruction.opcode {
+                Opcode::Add | Opcode::Multiply => {
+                    let a = self.operand(&parameters[0], offset);
+                    let b = self.operand(&parameters[1], offset);
+                    let value = arithmetic(instruction.opcode, a, b);
+                    statements.push(Stmt::Assign(self.place(&parameters[2], offset), value));
+                },
+                Opcode::LessThan | Opcode::Equals => {
+                    let comparison = if instruction.opcode == Opcode::LessThan {
+                        Comparison::Less
+                    } else {
+                        Comparison::Equal
+                    };
+                    let value = Expr::Compare(comparison,
+                                              Box::new(self.operand(&parameters[0], offset)),
+                                              Box::new(self.operand(&parameters[1], offset)));
+                    match jump {
+                        Some(jump) if i + 1 == body && self.inlines(&parameters[2], jump) => {
+                            inlined = Some(value);
+                        },
+                        _ => statements.push(Stmt::Assign(self.place(&parameters[2], offset), value))
+                    }
+                },
+                Opcode::Input => statements.push(Stmt::Input(self.place(&parameters[0], offset))),
+                Opcode::Output => statements.push(Stmt::Output(self.operand(&parameters[0], offset))),
+                Opcode::RelativeBaseOffset => {
+                    let followed = moved(offset, &parameters[0]);
+                    if followed.is_none() {
+                        statements.push(Stmt::MoveBase(self.operand(&parameters[0], offset)));
+                    }
+                    offset = followed;
+                },
+                // Jumps in the middle of a block are never taken.
+                Opcode::JumpIfTrue | Opcode::JumpIfFalse | Opcode::Halt => {}
+            }
+        }
+
+        let end = match (block.exit, jump) {
+            (Exit::Halt, _) => End::Stop(Stmt::Halt),
+            (Exit::Fallthrough(next), _) => End::Next(next),
+            (Exit::Jump(target), _) => match call {
+                Some(call) => {
+                    let arguments = (1..=call.arguments)
+                        .map(|n| self.place(&Parameter {
+                            value: V::from_value(n as Value),
+                            mode: ParameterMode::Relative
+                        }, offset))
+                        .collect();
+                    statements.push(Stmt::Call(target, arguments));
+                    End::Next(call.returns_to)
+                },
+                None => End::Next(target)
+            },
+            (Exit::Branch { taken, not_taken }, Some(jump)) => {
+                End::Branch(self.condition(jump, offset, inlined), taken, not_taken)
+            },
+            (Exit::Dynamic { not_taken }, Some(jump)) => {
+                let target = &jump.parameters()[1];
+                let leaves = match self.place(target, offset) {
+                    Place::Slot(0) => Stmt::Return,
+                    Place::Relative(ref relative) if !self.main && relative.is_zero() => Stmt::Return,
+                    _ => Stmt::JumpTo(self.operand(target, offset))
+                };
+                match not_taken {
+                    Some(next) => End::Escape(self.condition(jump, offset, inlined), leaves, next),
+                    None => End::Stop(leaves)
+                }
+            },
+            (Exit::Invalid(address), _) => End::Stop(Stmt::Invalid(address)),
+            (Exit::Branch { .. }, None) | (Exit::Dynamic { .. }, None) => {
+                unreachable!("branches and dynamic jumps always end in a jump")
+            }
+        };
+
+        Block { statements, end }
+    }
+}
+
+// Puts one function's blocks back together into ifs and loops.
+struct Structurer<'a, V> {
+    blocks: &'a BTreeMap<Address, Block<V>>,
+    // Where both sides of each branch come back together.
+    joins: HashMap<Address, Address>,
+    // Loop headers, and where control goes when their loop exits.
+    follows: HashMap<Address, Option<Address>>,
+    visited: HashSet<Address>,
+    // The loops we're inside of, innermost last.
+    loops: Vec<Address>
+}
+
+impl<'a, V: Number> Structurer<'a, V> {
+    fn new(entry: Address, blocks: &'a BTreeMap<Address, Block<V>>) -> Structurer<'a, V> {
+        let addresses: Vec<Address> = blocks.keys().copied().collect();
+        let index: HashMap<Address, usize> = addresses.iter()
+            .enumerate()
+            .map(|(i, &address)| (address, i))
+            .collect();
+        let successors: Vec<Vec<usize>> = addresses.iter()
+            .map(|address| blocks[address].end.successors().iter().map(|s| index[s]).collect())
+            .collect();
+        let idoms = immediate_dominators(index[&entry], &successors);
+
+        // Postdominators are dominators going backwards from a made up exit
+        // node that everything leaving the function goes to.
+        let exit = addresses.len();
+        let mut backwards = vec![Vec::new(); exit + 1];
+        for (node, targets) in successors.iter().enumerate() {
+            for &target in targets {
+                backwards[target].push(node);
+            }
+            if blocks[&addresses[node]].end.leaves() {
+                backwards[exit].push(node);
+            }
+        }
+        let joins = immediate_dominators(exit, &backwards).into_iter()
+            .enumerate()
+            .filter_map(|(node, join)| match join {
+                Some(join) if node < exit && join < exit => Some((addresses[node], addresses[join])),
+                _ => None
+            })
+            .collect::<HashMap<Address, Address>>();
+
+        // A loop is everything that can get back round to a header without
+        // going through it again, where the header dominates the jump back.
+        let mut bodies: HashMap<usize, HashSet<usize>> = HashMap::new();
+        for (node, targets) in successors.iter().enumerate() {
+            for &header in targets.iter().filter(|&&header| dominates(&idoms, header, node)) {
+                let body = bodies.entry(header).or_insert_with(|| vec![header].into_iter().collect());
+                let mut unexplored = vec![node];
+                while let Some(member) = unexplored.pop() {
+                    if body.insert(member) {
+                        unexplored.extend(backwards[member].iter().filter(|&&p| p != exit));
+                    }
+                }
+            }
+        }
+        let follows = bodies.into_iter()
+            .map(|(header, body)| {
+                let exits: BTreeSet<Address> = body.iter()
+                    .flat_map(|&member| successors[member].iter())
+                    .filter(|successor| !body.contains(successor))
+                    .map(|&successor| addresses[successor])
+                    .collect();
+                let header = addresses[header];
+                let follow = match joins.get(&header) {
+                    Some(join) if exits.contains(join) => Some(*join),
+                    _ => exits.iter().next().copied()
+                };
+                (header, follow)
+            })
+            .collect();
+
+        Structurer { blocks, joins, follows, visited: HashSet::new(), loops: Vec::new() }
+    }
+
+    // What going to target turns into if it doesn't mean carrying on with
+    // target's code right here: nothing at the end of the region, or a
+    // break, continue or goto.
+    fn jump(&self, target: Address, stop: Option<Address>) -> Option<Option<Stmt<V>>> {
+        if Some(target) == stop {
+            return Some(None);
+        }
+        if let Some(&header) = self.loops.last() {
+            if target == header {
+                return Some(Some(Stmt::Continue));
+            }
+            if self.follows[&header] == Some(target) {
+                return Some(Some(Stmt::Break));
+            }
+        }
+        if self.visited.contains(&target) {
+            return Some(Some(Stmt::Goto(target)));
+        }
+        None
+    }
+
+    fn sequence(&mut self, start: Address, stop: Option<Address>) -> Vec<Stmt<V>> {
+        let mut statements = Vec::new();
+        self.continue_from(Some(start), stop, &mut statements);
+        statements
+    }
+
+    fn continue_from(&mut self, mut next: Option<Address>, stop: Option<Address>,
+                     statements: &mut Vec<Stmt<V>>) {
+        while let Some(address) = next {
+            match self.jump(address, stop) {
+                Some(jump) => {
+                    statements.extend(jump);
+                    return;
+                },
+                None => next = self.emit(address, stop, statements)
+            }
+        }
+    }
+
+    // Emits the block at address, or the whole loop if it's a loop header,
+    // and returns where to carry on from.
+    fn emit(&mut self, address: Address, stop: Option<Address>, statements: &mut Vec<Stmt<V>>)
+            -> Option<Address> {
+        self.visited.insert(address);
+        statements.push(Stmt::Label(address));
+
+        let follow = match self.follows.get(&address) {
+            Some(&follow) => follow,
+            None => return self.block(address, stop, statements)
+        };
+
+        // Loops only end with a break, so there's no stop inside them.
+        self.loops.push(address);
+        let mut body = Vec::new();
+        let next = self.block(address, None, &mut body);
+        self.continue_from(next, None, &mut body);
+        self.loops.pop();
+
+        statements.push(structure_loop(body));
+        follow
+    }
+
+    fn block(&mut self, address: Address, stop: Option<Address>, statements: &mut Vec<Stmt<V>>)
+             -> Option<Address> {
+        let block = &self.blocks[&address];
+        statements.extend(block.statements.iter().cloned());
+
+        match block.end.clone() {
+            End::Stop(leaves) => {
+                statements.push(leaves);
+                None
+            },
+            End::Next(next) => Some(next),
+            End::Escape(condition, leaves, next) => {
+                statements.push(Stmt::If(condition, vec![leaves], Vec::new()));
+                Some(next)
+            },
+            End::Branch(condition, taken, not_taken) => {
+                // A side that's just a break, continue or goto becomes an
+                // early exit, and the other side carries on after it.
+                if let Some(Some(jump)) = self.jump(taken, stop) {
+                    statements.push(Stmt::If(condition, vec![jump], Vec::new()));
+                    return Some(not_taken);
+                }
+                if let Some(Some(jump)) = self.jump(not_taken, stop) {
+                    statements.push(Stmt::If(condition.negate(), vec![jump], Vec::new()));
+                    return Some(taken);
+                }
+
+                let join = self.joins.get(&address).copied();
+                let inner = join.or(stop);
+                let then = if Some(taken) == join { Vec::new() } else { self.sequence(taken, inner) };
+                let otherwise = if Some(not_taken) == join {
+                    Vec::new()
+                } else {
+                    self.sequence(not_taken, inner)
+                };
+                match (then.is_empty(), otherwise.is_empty()) {
+                    (true, true) => {},
+                    (true, false) => statements.push(Stmt::If(condition.negate(), otherwise, Vec::new())),
+                    _ => statements.push(Stmt::If(condition, then, otherwise))
+                }
+                join
+            }
+        }
+    }
+}
+
+struct Function<V> {
+    entry: Address,
+    main: bool,
+    arguments: usize,
+    body: Vec<Stmt<V>>
+}
+
+impl<V: Number> Function<V> {
+    fn new(entry: Address, main: bool, arguments: usize, graph: &ControlFlowGraph<V>,
+           calls: &BTreeMap<Address, Call>, reads: &HashMap<V, usize>) -> Function<V> {
+        let translator = Translator { main, reads };
+        let successors = |block: &BasicBlock<V>| match calls.get(&block.start) {
+            Some(call) => vec![call.returns_to],
+            None => block.exit.successors()
+        };
+
+        // Everything reachable from the entry without going into calls, and
+        // the offset of the relative base at the start of each block. Where
+        // paths disagree about the offset we've lost track of it.
+        let mut offsets: BTreeMap<Address, Option<Value>> = BTreeMap::new();
+        offsets.insert(entry, Some(0));
+        let mut unexplored = vec![entry];
+        while let Some(address) = unexplored.pop() {
+            let block = graph.block(address).expect("successors are always blocks");
+            let offset = translator.offset_after(block, offsets[&address]);
+            for successor in successors(block) {
+                let merged = match offsets.get(&successor) {
+                    None => offset,
+                    Some(known) if known.is_none() || *known == offset => continue,
+                    Some(_) => None
+                };
+                offsets.insert(successor, merged);
+                unexplored.push(successor);
+            }
+        }
+
+        let blocks: BTreeMap<Address, Block<V>> = offsets.iter()
+            .map(|(&address, &offset)| {
+                let block = graph.block(address).expect("successors are always blocks");
+                (address, translator.translate(block, offset, calls.get(&address)))
+            })
+            .collect();
+        let body = Structurer::new(entry, &blocks).sequence(entry, None);
+
+        Function { entry, main, arguments, body }
+    }
+
+    fn name(&self) -> String {
+        if self.main { "main".to_string() } else { format!("func_{}", self.entry) }
+    }
+
+    fn render(&self) -> String {
+        let mut labels = HashSet::new();
+        gotos(&self.body, &mut labels);
+        let renderer = Renderer { arguments: self.arguments, labels };
+
+        let parameters: Vec<String> = (1..=self.arguments).map(|n| format!("arg{}", n)).collect();
+        let mut lines = vec![format!("{}({}) {{", self.name(), parameters.join(", "))];
+        renderer.statements(&self.body, 1, &mut lines);
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+fn gotos<V>(statements: &[Stmt<V>], targets: &mut HashSet<Address>) {
+    for statement in statements {
+        match statement {
+            Stmt::Goto(target) => {
+                targets.insert(*target);
+            },
+            Stmt::If(_, then, otherwise) => {
+                gotos(then, targets);
+                gotos(otherwise, targets);
+            },
+            Stmt::Loop(body) | Stmt::While(_, body) | Stmt::DoWhile(body, _) => gotos(body, targets),
+            _ => {}
+        }
+    }
+}
+
+struct Renderer {
+    arguments: usize,
+    labels: HashSet<Address>
+}
+
+impl Renderer {
+    fn place<V: Number>(&self, place: &Place<V>) -> String {
+        match place {
+            Place::Memory(address) => format!("m[{}]", address),
+            Place::Slot(0) => "ret".to_string(),
+            Place::Slot(slot) if *slot < 0 => format!("frame[{}]", slot),
+            Place::Slot(slot) if *slot as usize <= self.arguments => format!("arg{}", slot),
+            Place::Slot(slot) => format!("local{}", *slot as usize - self.arguments),
+            Place::Relative(offset) => format!("rb[{}]", offset)
+        }
+    }
+
+    fn expr<V: Number>(&self, expr: &Expr<V>) -> String {
+        match expr {
+            Expr::Const(value) => value.to_string(),
+            Expr::Load(place) => self.place(place),
+            Expr::Add(a, b) => match b.as_ref() {
+                Expr::Const(value) if value.is_negative() => {
+                    match value.to_value().and_then(|v| v.checked_neg()) {
+                        Some(positive) => format!("{} - {}", self.operand(a), positive),
+                        None => format!("{} + {}", self.operand(a), value)
+                    }
+                },
+                _ => format!("{} + {}", self.operand(a), self.operand(b))
+            },
+            Expr::Multiply(a, b) => format!("{} * {}", self.operand(a), self.operand(b)),
+            Expr::Compare(comparison, a, b) => {
+                format!("{} {} {}", self.operand(a), comparison.symbol(), self.operand(b))
+            }
+        }
+    }
+
+    // Anything more than a single value gets brackets when it's part of
+    // something bigger.
+    fn operand<V: Number>(&self, expr: &Expr<V>) -> String {
+        match expr {
+            Expr::Const(_) | Expr::Load(_) => self.expr(expr),
+            _ => format!("({})", self.expr(expr))
+        }
+    }
+
+    fn statements<V: Number>(&self, statements: &[Stmt<V>], depth: usize, lines: &mut Vec<String>) {
+        let indent = "    ".repeat(depth);
+        for statement in statements {
+            let line = match statement {
+                Stmt::Assign(place, value) => format!("{} = {};", self.place(place), self.expr(value)),
+                Stmt::Input(place) => format!("{} = input();", self.place(place)),
+                Stmt::Output(value) => format!("output({});", self.expr(value)),
+                Stmt::MoveBase(change) => format!("rb += {};", self.expr(change)),
+                Stmt::Call(target, arguments) => {
+                    let arguments: Vec<String> = arguments.iter().map(|a| self.place(a)).collect();
+                    format!("func_{}({});", target, arguments.join(", "))
+                },
+                Stmt::Return => "return;".to_string(),
+                Stmt::JumpTo(target) => format!("goto *{};", self.operand(target)),
+                Stmt::Halt => "halt();".to_string(),
+                Stmt::Invalid(address) => format!("// no valid instruction at {}", address),
+                Stmt::Break => "break;".to_string(),
+                Stmt::Continue => "continue;".to_string(),
+                Stmt::Goto(target) => format!("goto label_{};", target),
+                Stmt::Label(address) if self.labels.contains(address) => {
+                    format!("label_{}:", address)
+                },
+                Stmt::Label(_) => continue,
+                Stmt::If(condition, then, otherwise) => {
+                    lines.push(format!("{}if ({}) {{", indent, self.expr(condition)));
+                    self.statements(then, depth + 1, lines);
+                    if !otherwise.is_empty() {
+                        lines.push(format!("{}}} else {{", indent));
+                        self.statements(otherwise, depth + 1, lines);
+                    }
+                    "}".to_string()
+                },
+                Stmt::Loop(body) => {
+                    lines.push(format!("{}while (true) {{", indent));
+                    self.statements(body, depth + 1, lines);
+                    "}".to_string()
+                },
+                Stmt::While(condition, body) => {
+                    lines.push(format!("{}while ({}) {{", indent, self.expr(condition)));
+                    self.statements(body, depth + 1, lines);
+                    "}".to_string()
+                },
+                Stmt::DoWhile(body, condition) => {
+                    lines.push(format!("{}do {{", indent));
+                    self.statements(body, depth + 1, lines);
+                    format!("}} while ({});", self.expr(condition))
+                }
+            };
+            lines.push(format!("{}{}", indent, line));
+        }
+    }
+}
+
+// Every function in the program, main first and then the rest in the order
+// they are in memory.
+pub fn decompile<V: Number>(slots: &[V]) -> String {
+    let (graph, calls) = graph_with_calls(slots);
+    let reads = positional_reads(&graph);
+
+    let mut arguments = BTreeMap::new();
+    arguments.insert(graph.entry(), 0);
+    for call in calls.values() {
+        let count = arguments.entry(call.target).or_insert(0);
+        *count = call.arguments.max(*count);
+    }
+
+    arguments.iter()
+        .map(|(&entry, &count)| {
+            let main = entry == graph.entry();
+            Function::new(entry, main, count, &graph, &calls, &reads).render()
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+impl<V: Number> Machine<V> {
+    pub fn decompile(&self) -> String {
+        decompile(&self.analysed_memory())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompiled(source: &str) -> String {
+        Machine::from_assembly(source).unwrap().decompile()
+    }
+
+    #[test]
+    fn conditions() {
+        assert_eq!("\
+main() {
+    m[16] = input();
+    if (m[16] < 10) {
+        output(0);
+        halt();
+    } else {
+        output(1);
+        halt();
+    }
+}", decompiled("
+                    IN   [x]
+                    LT   [x], #10, [small]
+                    JT   [small], #yes
+                    OUT  #1
+                    HLT
+            yes:    OUT  #0
+                    HLT
+            small:  DATA 0
+            x:      DATA 0
+        "));
+    }
+
+    #[test]
+    fn loops() {
+        assert_eq!("\
+main() {
+    m[26] = input();
+    while (m[26] != 0) {
+        output(m[26]);
+        m[26] = m[26] - 1;
+    }
+    do {
+        m[26] = m[26] + 2;
+    } while (m[26] < 10);
+    halt();
+}", decompiled("
+                    IN   [x]
+            top:    JF   [x], #next
+                    OUT  [x]
+                    ADD  [x], #-1, [x]
+                    JT   #1, #top
+            next:   ADD  [x], #2, [x]
+                    LT   [x], #10, [small]
+                    JT   [small], #next
+                    HLT
+            x:      DATA 0
+            small:  DATA 0
+        "));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!("\
+main() {
+    m[101] = input();
+    func_14(m[101]);
+    output(m[101]);
+    halt();
+}
+
+func_14(arg1) {
+    if (arg1 < 100) {
+        arg1 = arg1 * 2;
+    }
+    return;
+}", decompiled("
+                    RBO  #100
+                    IN   rb+1
+                    ADD  #ret, #0, rb+0
+                    JT   #1, #double
+            ret:    OUT  rb+1
+                    HLT
+            double: RBO  #2
+                    LT   rb-1, #100, [big]
+                    JF   [big], #done
+                    MUL  rb-1, #2, rb-1
+            done:   RBO  #-2
+                    JT   #1, rb+0
+            big:    DATA 0
+        "));
+    }
+
+    #[test]
+    fn unstructured_jumps() {
+        // Jumps into the middle of a loop from outside it.
+        let pseudocode = decompiled("
+                    IN   [x]
+                    JT   [x], #middle
+            top:    OUT  #1
+            middle: OUT  #2
+                    JT   #1, #top
+            x:      DATA 0
+        ");
+        assert!(pseudocode.contains("goto label_"), "{}", pseudocode);
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, Sender, SyncSender};
+
+use super::Value;
+use super::number::Number;
+
+// Something an Input instruction can ask for a value. Anything written to the
+// machine with Machine::write is used up first, and the device is only asked
+// once that runs out. None blocks the machine, the same as running out of
+// written input, and it'll ask again the next time it's run.
+pub trait InputDevice<V = Value>: Send {
+    fn next_input(&mut self) -> Option<V>;
+}
+
+// Something that gets every value an Output instruction produces, as soon as
+// it's produced. Values sent to a device don't show up in Machine::read.
+pub trait OutputDevice<V = Value>: Send {
+    fn output(&mut self, value: V);
+}
+
+// Shared handles to devices, for the same reason as Tracer: the caller keeps
+// a clone of the Arc to feed or look at the device while the machine runs.
+#[derive(Clone)]
+pub struct SharedInput<V = Value>(pub Arc<Mutex<dyn InputDevice<V>>>);
+
+#[derive(Clone)]
+pub struct SharedOutput<V = Value>(pub Arc<Mutex<dyn OutputDevice<V>>>);
+
+impl<V> SharedInput<V> {
+    pub fn next_input(&self) -> Option<V> {
+        // A device that panicked can't give us anything, which looks the
+        // same to the machine as having nothing to give yet.
+        self.0.lock().ok().and_then(|mut device| device.next_input())
+    }
+}
+
+impl<V> SharedOutput<V> {
+    pub fn output(&self, value: V) {
+        if let Ok(mut device) = self.0.lock() {
+            device.output(value);
+        }
+    }
+}
+
+impl<V> std::fmt::Debug for SharedInput<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SharedInput")
+    }
+}
+
+impl<V> std::fmt::Debug for SharedOutput<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SharedOutput")
+    }
+}
+
+impl<V: Send> InputDevice<V> for VecDeque<V> {
+    fn next_input(&mut self) -> Option<V> {
+        self.pop_front()
+    }
+}
+
+impl<V: Send> OutputDevice<V> for VecDeque<V> {
+    fn output(&mut self, value: V) {
+        self.push_back(value);
+    }
+}
+
+impl<V: Send> OutputDevice<V> for Vec<V> {
+    fn output(&mut self, value: V) {
+        self.push(value);
+    }
+}
+
+// Closures can't implement the device traits directly without getting in
+// the way of every other implementation, so they go in these.
+pub struct InputFn<F>(pub F);
+
+impl<V, F> InputDevice<V> for InputFn<F>
+where F: FnMut() -> Option<V> + Send {
+    fn next_input(&mut self) -> Option<V> {
+        (self.0)()
+    }
+}
+
+pub struct OutputFn<F>(pub F);
+
+impl<V, F> OutputDevice<V> for OutputFn<F>
+where F: FnMut(V) + Send {
+    fn output(&mut self, value: V) {
+        (self.0)(value)
+    }
+}
+
+// Blocks the machine for good once the iterator runs out.
+pub struct IterInput<I>(pub I);
+
+impl<V, I> InputDevice<V> for IterInput<I>
+where I: Iterator<Item=V> + Send {
+    fn next_input(&mut self) -> Option<V> {
+        self.0.next()
+    }
+}
+
+// Never waits for the sender, so a machine reading from a channel blocks
+// whenever the channel is empty and picks up where it left off once there's
+// something in it and it's run again.
+impl<V: Send> InputDevice<V> for Receiver<V> {
+    fn next_input(&mut self) -> Option<V> {
+        self.try_recv().ok()
+    }
+}
+
+// Output to a channel nobody is listening to any more is dropped.
+impl<V: Send> OutputDevice<V> for Sender<V> {
+    fn output(&mut self, value: V) {
+        let _ = self.send(value);
+    }
+}
+
+impl<V: Send> OutputDevice<V> for SyncSender<V> {
+    fn output(&mut self, value: V) {
+        let _ = self.send(value);
+    }
+}
+
+// One number per line. Unlike the other devices this does wait, for a whole
+// line of stdin. Lines that aren't numbers are complained about and skipped,
+// and the end of stdin blocks the machine.
+pub struct StdinInput;
+
+impl<V: Number> InputDevice<V> for StdinInput {
+    fn next_input(&mut self) -> Option<V> {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line = line.ok()?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.parse() {
+                Ok(value) => return Some(value),
+                Err(_) => eprintln!("Ignoring `{}`, expected a number.", line)
+            }
+        }
+        None
+    }
+}
+
+// One number per line.
+pub struct StdoutOutput;
+
+impl<V: Number> OutputDevice<V> for StdoutOutput {
+    fn output(&mut self, value: V) {
+        println!("{}", value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use super::super::{Machine, MachineState, OperationalError, RunOutcome};
+
+    // Doubles every input until it gets a zero.
+    fn doubler() -> Machine {
+        Machine::from_slots(vec![3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0])
+    }
+
+    #[test]
+    fn queues() -> Result<(), OperationalError> {
+        let input = Arc::new(Mutex::new(VecDeque::from(vec![1, 2, 3])));
+        let output = Arc::new(Mutex::new(Vec::new()));
+
+        let mut machine = doubler();
+        machine.write(5);
+        machine.input_from(input.clone());
+        machine.output_to(output.clone());
+
+        // Written input comes first, then the device.
+        assert_eq!(RunOutcome::Blocked, machine.run()?);
+        assert_eq!(vec![10, 2, 4, 6], *output.lock().unwrap());
+        assert!(machine.read().is_empty());
+
+        input.lock().unwrap().push_back(0);
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn closures_react_to_each_value() -> Result<(), OperationalError> {
+        // Feeds back whatever came out last, so 1 doubles up to 16 and then
+        // the closure stops it with a zero.
+        let last = Arc::new(Mutex::new(1));
+        let seen = last.clone();
+        let mut machine = doubler();
+        machine.input_from(Arc::new(Mutex::new(InputFn(move || {
+            let last = *seen.lock().unwrap();
+            Some(if last >= 16 { 0 } else { last })
+        }))));
+        let record = last.clone();
+        machine.output_to(Arc::new(Mutex::new(OutputFn(move |value| {
+            *record.lock().unwrap() = value;
+        }))));
+
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        assert_eq!(16, *last.lock().unwrap());
+
+        Ok(())
+    }
+
+    #[test]
+    fn iterators() -> Result<(), OperationalError> {
+        let mut machine = doubler();
+        machine.input_from(Arc::new(Mutex::new(IterInput((1..4).chain(0..1)))));
+        machine.run()?;
+
+        assert_eq!(MachineState::Halted, machine.state());
+        assert_eq!(vec![2, 4, 6], machine.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn channels() -> Result<(), OperationalError> {
+        let (input, receiver) = mpsc::channel();
+        let (sender, output) = mpsc::channel();
+
+        let mut machine = doubler();
+        machine.input_from(Arc::new(Mutex::new(receiver)));
+        machine.output_to(Arc::new(Mutex::new(sender)));
+
+        assert_eq!(RunOutcome::Blocked, machine.run()?);
+        input.send(21).unwrap();
+        assert_eq!(RunOutcome::Blocked, machine.run()?);
+        assert_eq!(Ok(42), output.try_recv());
+
+        input.send(0).unwrap();
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        assert!(output.try_recv().is_err());
+
+        Ok(())
+    }
+}
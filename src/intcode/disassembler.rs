@@ -0,0 +1,157 @@
+use super::{Address, Value, Machine, Instruction, OperationalError, ParameterMode};
+use super::number::Number;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Line<V = Value> {
+    Instruction { address: Address, instruction: Instruction<V> },
+    // Anything that doesn't decode to a valid instruction is shown as a raw
+    // word instead. Intcode freely mixes code and data, so this is expected.
+    Data { address: Address, value: V }
+}
+
+impl<V> Line<V> {
+    pub fn address(&self) -> Address {
+        match self {
+            Line::Instruction { address, .. } => *address,
+            Line::Data { address, .. } => *address
+        }
+    }
+}
+
+impl<V: Number> std::fmt::Display for Line<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Line::Instruction { address, instruction } => {
+                write!(f, "{:>5}: {}", address, instruction)
+            },
+            Line::Data { address, value } => {
+                write!(f, "{:>5}: {:<4} {}", address, "DATA", value)
+            }
+        }
+    }
+}
+
+// Decodes the instruction starting at address without touching the machine,
+// so unlike Machine::read_instruction this never grows memory. Returns None
+// for anything the machine would refuse to execute.
+pub fn decode_at<V: Number>(slots: &[V], address: Address) -> Option<Instruction<V>> {
+    decode_with(|address| slots.get(address), address)
+}
+
+// The same, but for words that aren't in a slice.
+fn decode_with<'a, V, F>(word: F, address: Address) -> Option<Instruction<V>>
+where V: Number, F: Fn(Address) -> Option<&'a V> {
+    let instruction = Instruction::decode(word(address)?, |i| {
+        let parameter_address = address + i + 1;
+        word(parameter_address)
+            .cloned()
+            .ok_or(OperationalError::OutOfRange(parameter_address))
+    }).ok()?;
+
+    if instruction.opcode.writes_last_parameter() &&
+       instruction.parameters().last()?.mode == ParameterMode::Immediate {
+        return None;
+    }
+
+    Some(instruction)
+}
+
+// A linear sweep from the start of memory. Every word ends up in exactly one
+// line, either as part of an instruction or as DATA.
+pub fn disassemble<V: Number>(slots: &[V]) -> Vec<Line<V>> {
+    disassemble_from(slots, 0)
+}
+
+pub fn disassemble_from<V: Number>(slots: &[V], start: Address) -> Vec<Line<V>> {
+    sweep(|address| slots.get(address), start, slots.len())
+}
+
+fn sweep<'a, V, F>(word: F, start: Address, end: Address) -> Vec<Line<V>>
+where V: Number, F: Fn(Address) -> Option<&'a V> {
+    let mut lines = Vec::new();
+    let mut address = start;
+
+    while address < end {
+        match decode_with(&word, address) {
+            Some(instruction) => {
+                let width = instruction.opcode.parameter_count() + 1;
+                lines.push(Line::Instruction { address, instruction });
+                address += width;
+            },
+            None => {
+                // Unused paged memory reads as zero like everywhere else.
+                let value = word(address).cloned().unwrap_or_default();
+                lines.push(Line::Data { address, value });
+                address += 1;
+            }
+        }
+    }
+
+    lines
+}
+
+pub fn listing<V: Number>(lines: &[Line<V>]) -> String {
+    lines.iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+impl<V: Number> Machine<V> {
+    pub fn disassemble(&self) -> Vec<Line<V>> {
+        self.disassemble_from(0)
+    }
+
+    pub fn disassemble_from(&self, start: Address) -> Vec<Line<V>> {
+        sweep(|address| self.slots.get(address), start, self.analysis_end())
+    }
+
+    pub fn listing(&self) -> String {
+        listing(&self.disassemble())
+    }
+
+    pub fn current_instruction(&self) -> Option<Instruction<V>> {
+        decode_with(|address| self.slots.get(address), self.pointer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simple_program() {
+        let machine = Machine::from_slots(vec![1,9,10,3,2,3,11,0,99,30,40,50]);
+
+        assert_eq!(
+            "    0: ADD  [9], [10], [3]\n\
+             \x20   4: MUL  [3], [11], [0]\n\
+             \x20   8: HLT\n\
+             \x20   9: DATA 30\n\
+             \x20  10: DATA 40\n\
+             \x20  11: DATA 50",
+            machine.listing()
+        );
+    }
+
+    #[test]
+    fn parameter_modes() {
+        let lines = disassemble::<Value>(&[21201, 3, -2, 5, 109, 4, 204, -1]);
+
+        assert_eq!(3, lines.len());
+        assert_eq!("    0: ADD  rb+3, #-2, rb+5", lines[0].to_string());
+        assert_eq!("    4: RBO  #4", lines[1].to_string());
+        assert_eq!("    6: OUT  rb-1", lines[2].to_string());
+    }
+
+    #[test]
+    fn undecodable_words_are_data() {
+        // 1101 with only two words after it, 11101 writes to an immediate and
+        // 10099 has too many mode digits for a halt.
+        let lines = disassemble::<Value>(&[11101, 0, 0, 0, 10099, 1101, 0, 0]);
+
+        assert!(lines.iter().all(|line| matches!(line, Line::Data { .. })));
+        assert_eq!(8, lines.len());
+        assert_eq!(5, lines[5].address());
+    }
+}
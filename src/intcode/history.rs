@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+use super::{Address, Machine, MachineState};
+use super::number::Number;
+
+// Everything one instruction changed, as it was before the instruction ran,
+// so it can be put back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Undo<V> {
+    pointer: Address,
+    relative_base: V,
+    state: MachineState,
+    instruction_counter: usize,
+    input_pointer: usize,
+    output_length: usize,
+    // The address written to and what used to be there.
+    write: Option<(Address, V)>
+}
+
+// An undo log of every instruction executed while recording, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct History<V> {
+    undos: VecDeque<Undo<V>>,
+    limit: Option<usize>,
+    // The old value for the instruction that's executing right now.
+    pending_write: Option<(Address, V)>
+}
+
+impl<V: Number> History<V> {
+    pub(super) fn wrote(&mut self, address: Address, old_value: V) {
+        self.pending_write = Some((address, old_value));
+    }
+}
+
+impl<V: Number> Machine<V> {
+    // Starts keeping enough of a log to step back over every instruction
+    // from here on, or only the last limit of them. That's one entry per
+    // instruction, so a long run without a limit can use a lot of memory.
+    //
+    // Input that gets stepped back over is read again when the machine runs
+    // forwards, and output that gets stepped back over is taken back, even
+    // if it's been read. Output that's gone to a device is already gone,
+    // though.
+    pub fn start_recording(&mut self, limit: Option<usize>) {
+        self.history = Some(History { undos: VecDeque::new(), limit, pending_write: None });
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.history = None;
+    }
+
+    pub fn recording(&self) -> bool {
+        self.history.is_some()
+    }
+
+    // How many instructions can be stepped back over.
+    pub fn history_len(&self) -> usize {
+        self.history.as_ref().map_or(0, |history| history.undos.len())
+    }
+
+    // Called before executing each instruction. None when not recording.
+    pub(super) fn begin_undo(&mut self) -> Option<Undo<V>> {
+        let history = self.history.as_mut()?;
+        history.pending_write = None;
+        Some(Undo {
+            pointer: self.pointer,
+            relative_base: self.relative_base.clone(),
+            state: self.state,
+            instruction_counter: self.instruction_counter,
+            input_pointer: self.input_pointer,
+            output_length: self.output.len(),
+            write: None
+        })
+    }
+
+    // Called after executing an instruction successfully. Blocked Inputs
+    // didn't do anything so there's nothing to undo.
+    pub(super) fn finish_undo(&mut self, mut undo: Undo<V>) {
+        if self.state == MachineState::Blocked {
+            return;
+        }
+        if let Some(history) = &mut self.history {
+            undo.write = history.pending_write.take();
+            history.undos.push_back(undo);
+            if let Some(limit) = history.limit {
+                while history.undos.len() > limit {
+                    history.undos.pop_front();
+                }
+            }
+        }
+    }
+
+    // Undoes the last instruction executed. False if there's nothing to
+    // undo, because recording is off or has gone as far back as it can.
+    pub fn step_back(&mut self) -> bool {
+        let undo = match self.history.as_mut().and_then(|history| history.undos.pop_back()) {
+            Some(undo) => undo,
+            None => return false
+        };
+
+        if let Some((address, value)) = undo.write {
+            // The address was written to once already, so it's allocated
+            // and this can't go over the memory limit.
+            self.set(address, value).expect("undoing a write to allocated memory");
+        }
+        self.pointer = undo.pointer;
+        self.relative_base = undo.relative_base;
+        self.state = undo.state;
+        self.input_pointer = undo.input_pointer;
+        self.output.truncate(undo.output_length);
+        self.output_pointer = self.output_pointer.min(undo.output_length);
+        self.instruction_counter = undo.instruction_counter;
+
+        // Anything else remembering where the machine has been is wrong
+        // about it now.
+        self.resume_address = None;
+        self.triggered = None;
+        if self.loop_detector.is_some() {
+            self.set_loop_detection(true);
+        }
+
+        true
+    }
+
+    // Steps back until instruction_counter is instruction_count. False if
+    // the history doesn't go back that far, in which case the machine is as
+    // far back as it could get, or if there was never a state with that
+    // count. Blocked Inputs count as instructions but can't be stepped back
+    // to, since they didn't change anything.
+    pub fn rewind_to(&mut self, instruction_count: usize) -> bool {
+        while self.instruction_counter > instruction_count {
+            if !self.step_back() {
+                return false;
+            }
+        }
+        self.instruction_counter == instruction_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::OperationalError;
+
+    // Doubles every input until it gets a zero.
+    fn doubler() -> Machine {
+        Machine::from_slots(vec![3, 15, 1006, 15, 14, 1002, 15, 2, 15, 4, 15, 1105, 1, 0, 99, 0])
+    }
+
+    #[test]
+    fn stepping_back() -> Result<(), OperationalError> {
+        let mut machine = doubler();
+        machine.start_recording(None);
+        machine.write(3);
+        machine.run()?;
+        let before = machine.clone();
+
+        machine.write(5);
+        machine.run()?;
+        assert_eq!(vec![6, 10], machine.read());
+        assert_eq!(10, machine.history_len());
+
+        // Back over everything the 5 did.
+        for _ in 0..5 {
+            assert!(machine.step_back());
+        }
+        assert_eq!(before.pointer(), machine.pointer());
+        assert_eq!(before.memory(), machine.memory());
+        assert_eq!(&[5], machine.pending_input());
+        assert!(machine.read().is_empty());
+
+        // Running forwards again does the same thing over.
+        machine.run()?;
+        assert_eq!(vec![10], machine.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rewinding() -> Result<(), OperationalError> {
+        let mut machine = doubler();
+        machine.start_recording(None);
+        for value in &[1, 2, 0] {
+            machine.write(*value);
+        }
+        machine.run()?;
+        assert_eq!(MachineState::Halted, machine.state());
+
+        assert!(machine.rewind_to(5));
+        assert_eq!(5, machine.instruction_counter());
+        assert_eq!(vec![2], machine.read());
+        assert_eq!(&[2, 0], machine.pending_input());
+
+        assert!(machine.rewind_to(0));
+        assert_eq!(doubler().memory(), machine.memory());
+        assert!(!machine.step_back());
+
+        Ok(())
+    }
+
+    #[test]
+    fn limited_history() -> Result<(), OperationalError> {
+        let mut machine = doubler();
+        machine.start_recording(Some(3));
+        machine.write(1);
+        machine.write(0);
+        machine.run()?;
+
+        assert_eq!(3, machine.history_len());
+        assert!(!machine.rewind_to(0));
+        assert_eq!(5, machine.instruction_counter());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::{Address, Instruction, Machine, MachineState, Opcode};
+use super::number::Number;
+
+// A loop that runs long enough without any I/O could fill up memory with
+// states, so past this many they're thrown away and we start again. Any
+// loop shorter than this still gets caught, just a bit later.
+const MAX_STATES: usize = 1 << 20;
+
+// Remembers what the machine looked like every time it jumped backwards
+// since its last input or output. The only way a program can get back to a
+// state it's already been in without any I/O in between is by looping
+// forever, since nothing else can change what it does next.
+//
+// States are remembered by hash rather than copied, so in theory two
+// different states could collide and look like a loop. With 64 bit hashes
+// that's not something to lose sleep over.
+#[derive(Debug, Clone, Default)]
+pub struct LoopDetector {
+    // State hash to the instruction count it was seen at.
+    seen: HashMap<u64, usize>
+}
+
+impl<V: Number> Machine<V> {
+    // With loop detection on, run stops with RunOutcome::InfiniteLoop as soon
+    // as the program gets back to a state it's already been in. It costs a
+    // hash of the whole of memory on every backward jump, so it's off by
+    // default.
+    pub fn set_loop_detection(&mut self, enabled: bool) {
+        self.loop_detector = if enabled { Some(LoopDetector::default()) } else { None };
+    }
+
+    pub fn loop_detection(&self) -> bool {
+        self.loop_detector.is_some()
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.pointer.hash(&mut hasher);
+        self.relative_base.hash(&mut hasher);
+        self.slots.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // Called after executing instruction from address. Some(period) if the
+    // machine has just proved it's stuck, where period is how many
+    // instructions it took to get back round to the same state.
+    pub(super) fn check_for_loop(&mut self, address: Address, instruction: &Instruction<V>)
+                                 -> Option<usize> {
+        if self.state != MachineState::Running {
+            return None;
+        }
+
+        match instruction.opcode {
+            Opcode::Input | Opcode::Output => {
+                self.loop_detector.as_mut()?.seen.clear();
+                None
+            },
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse if self.pointer <= address => {
+                let hash = self.state_hash();
+                let counter = self.instruction_counter;
+                let detector = self.loop_detector.as_mut()?;
+
+                if let Some(seen) = detector.seen.get(&hash) {
+                    return Some(counter - seen);
+                }
+                if detector.seen.len() >= MAX_STATES {
+                    detector.seen.clear();
+                }
+                detector.seen.insert(hash, counter);
+                None
+            },
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use super::super::{OperationalError, RunOutcome};
+
+    #[test]
+    fn catches_loops() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1105, 1, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(RunOutcome::InfiniteLoop { address: 0, period: 1 }, machine.run()?);
+
+        // Adds one to [11] and takes it away again, forever.
+        let mut machine = Machine::from_slots(vec![1001, 11, 1, 11, 1001, 11, -1, 11, 1105, 1, 0, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(RunOutcome::InfiniteLoop { address: 0, period: 3 }, machine.run()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn loops_that_get_somewhere() -> Result<(), OperationalError> {
+        // Counts [12] up to 1000 and halts.
+        let mut machine = Machine::from_slots(vec![1001, 12, 1, 12, 1007, 12, 1000, 13, 1005, 13, 0, 99, 0, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+
+        // Outputs 1 forever, which isn't stuck since something might be
+        // reading it.
+        let mut machine = Machine::from_slots(vec![104, 1, 1105, 1, 0]);
+        machine.set_loop_detection(true);
+        assert_eq!(RunOutcome::BudgetExhausted, machine.run_for(10_000)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn timeouts() -> Result<(), OperationalError> {
+        // Counts forever without ever repeating itself.
+        let mut machine = Machine::from_slots(vec![1001, 7, 1, 7, 1105, 1, 0, 0]);
+        machine.set_loop_detection(true);
+        machine.set_timeout(Some(Duration::from_millis(20)));
+        assert_eq!(RunOutcome::TimedOut, machine.run()?);
+        assert!(machine.instruction_counter() > 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::{Address, OperationalError};
+
+// How many values a machine can use before it fails with a
+// MemoryLimitExceeded error, unless told otherwise. That's a lot more than any
+// puzzle needs, but small enough that a stray write to some enormous address
+// fails instead of taking the whole process down trying to allocate it.
+pub const DEFAULT_MEMORY_LIMIT: usize = 1 << 24;
+
+// Paged memory is allocated this many values at a time. Forked machines copy
+// a whole page the first time they write to it, so this is on the small side.
+pub const PAGE_SIZE: usize = 256;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backend {
+    // One Vec covering every address from zero up to the highest one used.
+    // Fastest, and what every puzzle wants.
+    Dense,
+    // Only the pages that have actually been used, so a program can use
+    // addresses in the billions as long as it doesn't use too many of them.
+    // Pages are copy on write, which is what makes Machine::fork cheap.
+    Paged
+}
+
+// A machine's memory. Every address reads as zero until something writes to
+// it. Dense memory allocates everything up to any address that's read or
+// written, paged memory only the pages that get written to.
+//
+// This is an enum rather than a trait so that the dense case, which is every
+// instruction of every puzzle, doesn't pay for a virtual call on each access.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Memory<V> {
+    Dense(Vec<V>),
+    Paged(PagedMemory<V>)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PagedMemory<V> {
+    pages: BTreeMap<usize, Arc<Vec<V>>>,
+    // One past the highest address written to, as for a Vec.
+    len: usize,
+    // What every address in a page that hasn't been allocated reads as.
+    zero: V
+}
+
+impl<V: Clone + Default + PartialEq> PagedMemory<V> {
+    fn from_vec(values: Vec<V>) -> Self {
+        let zero = V::default();
+        let mut memory = PagedMemory { pages: BTreeMap::new(), len: values.len(), zero: zero.clone() };
+        // Pages of nothing but zeros don't need allocating. Nothing needs a
+        // limit either, since these values all fit in memory already.
+        for (address, value) in values.into_iter().enumerate().filter(|(_, v)| *v != zero) {
+            *memory.slot(address, None).unwrap() = value;
+        }
+        memory
+    }
+
+    fn get(&self, address: Address) -> Option<&V> {
+        self.pages.get(&(address / PAGE_SIZE))
+            .map(|page| &page[address % PAGE_SIZE])
+            .filter(|_| address < self.len)
+    }
+
+    fn slot(&mut self, address: Address, limit: Option<usize>) -> Result<&mut V, OperationalError> {
+        let number = address / PAGE_SIZE;
+        if !self.pages.contains_key(&number) {
+            let needed = (self.pages.len() + 1) * PAGE_SIZE;
+            if let Some(limit) = limit.filter(|limit| needed > *limit) {
+                return Err(OperationalError::MemoryLimitExceeded { address, limit });
+            }
+            self.pages.insert(number, Arc::new(vec![V::default(); PAGE_SIZE]));
+        }
+
+        self.len = self.len.max(address + 1);
+        // Copies the page first if some other machine is sharing it.
+        let page = Arc::make_mut(self.pages.get_mut(&number).unwrap());
+        Ok(&mut page[address % PAGE_SIZE])
+    }
+
+    fn to_vec_upto(&self, end: Address) -> Vec<V> {
+        let end = end.min(self.len);
+        let mut values = vec![V::default(); end];
+        for (number, page) in self.pages.range(..end.div_ceil(PAGE_SIZE)) {
+            let start = number * PAGE_SIZE;
+            let page_end = (start + PAGE_SIZE).min(end);
+            values[start..page_end].clone_from_slice(&page[..page_end - start]);
+        }
+        values
+    }
+
+    fn used_end(&self, end: Address) -> Address {
+        self.pages.range(..end.div_ceil(PAGE_SIZE))
+            .next_back()
+            .map_or(0, |(number, _)| ((number + 1) * PAGE_SIZE).min(self.len).min(end))
+    }
+}
+
+impl<V: Clone + Default + PartialEq> Memory<V> {
+    pub fn backend(&self) -> Backend {
+        match self {
+            Memory::Dense(_) => Backend::Dense,
+            Memory::Paged(_) => Backend::Paged
+        }
+    }
+
+    // One past the highest address used so far.
+    pub fn len(&self) -> usize {
+        match self {
+            Memory::Dense(values) => values.len(),
+            Memory::Paged(paged) => paged.len
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // How many values are actually allocated, which is what the memory limit
+    // applies to. For dense memory that's the same as len.
+    pub fn allocated(&self) -> usize {
+        match self {
+            Memory::Dense(values) => values.len(),
+            Memory::Paged(paged) => paged.pages.len() * PAGE_SIZE
+        }
+    }
+
+    // Unlike Machine::get, this never allocates, so addresses that haven't
+    // been used are None rather than zero.
+    pub fn get(&self, address: Address) -> Option<&V> {
+        match self {
+            Memory::Dense(values) => values.get(address),
+            Memory::Paged(paged) => paged.get(address)
+        }
+    }
+
+    // The value at address, allocating memory for it if needed.
+    pub(super) fn slot(&mut self, address: Address, limit: Option<usize>)
+                       -> Result<&mut V, OperationalError> {
+        match self {
+            Memory::Dense(values) => {
+                if address >= values.len() {
+                    if let Some(limit) = limit.filter(|limit| address >= *limit) {
+                        return Err(OperationalError::MemoryLimitExceeded { address, limit });
+                    }
+                    // +1 here so that address is a valid index, not the length.
+                    values.resize_with(address + 1, Default::default);
+                }
+                Ok(&mut values[address])
+            },
+            Memory::Paged(paged) => paged.slot(address, limit)
+        }
+    }
+
+    // Like slot, but without copying a shared page just to read from it.
+    pub(super) fn read(&mut self, address: Address, limit: Option<usize>)
+                       -> Result<&V, OperationalError> {
+        match self {
+            Memory::Dense(_) => self.slot(address, limit).map(|value| &*value),
+            // Reading doesn't use anything up, so there's no page to
+            // allocate and len stays where it is.
+            Memory::Paged(paged) => {
+                Ok(paged.pages.get(&(address / PAGE_SIZE))
+                    .map_or(&paged.zero, |page| &page[address % PAGE_SIZE]))
+            }
+        }
+    }
+
+    // Every value from zero up to len, with unused addresses as zero. Paged
+    // memory that's been written to at some enormous address makes that an
+    // enormous Vec, so anything that can see paged memory should use
+    // to_vec_upto instead.
+    pub fn to_vec(&self) -> Vec<V> {
+        self.to_vec_upto(self.len())
+    }
+
+    // The same, but stopping at end if that comes before len.
+    pub fn to_vec_upto(&self, end: Address) -> Vec<V> {
+        match self {
+            Memory::Dense(values) => values[..end.min(values.len())].to_vec(),
+            Memory::Paged(paged) => paged.to_vec_upto(end)
+        }
+    }
+
+    // One past the last allocated address before end, so everything from
+    // there up to end reads as zero.
+    pub fn used_end(&self, end: Address) -> Address {
+        match self {
+            Memory::Dense(values) => values.len().min(end),
+            Memory::Paged(paged) => paged.used_end(end)
+        }
+    }
+
+    // Every run of allocated memory, as its first address and its values, in
+    // order. For dense memory that's all of it at once, for paged memory
+    // it's a page at a time, which is how to look at all of paged memory
+    // without filling in the unused space.
+    pub fn chunks(&self) -> Vec<(Address, &[V])> {
+        match self {
+            Memory::Dense(values) => vec![(0, &values[..])],
+            Memory::Paged(paged) => paged.pages.iter()
+                .map(|(number, page)| {
+                    let start = number * PAGE_SIZE;
+                    (start, &page[..PAGE_SIZE.min(paged.len - start)])
+                })
+                .collect()
+        }
+    }
+
+    // Memory of len values with these chunks in it and zeros everywhere
+    // else. Paged memory gets exactly the pages the chunks are on. Nothing
+    // past len or over the limit gets allocated, so a bad snapshot can't
+    // take all the memory there is.
+    pub(super) fn from_chunks(backend: Backend, len: usize, limit: Option<usize>,
+                              chunks: Vec<(Address, Vec<V>)>) -> Result<Memory<V>, OperationalError> {
+        let end = |start: Address, chunk: &[V]| start.checked_add(chunk.len())
+            .filter(|end| *end <= len)
+            .ok_or(OperationalError::OutOfRange(start));
+
+        match backend {
+            Backend::Dense => {
+                if let Some(limit) = limit.filter(|limit| len > *limit) {
+                    return Err(OperationalError::MemoryLimitExceeded { address: len - 1, limit });
+                }
+                let mut values = vec![V::default(); len];
+                for (start, chunk) in chunks {
+                    let end = end(start, &chunk)?;
+                    values[start..end].clone_from_slice(&chunk);
+                }
+                Ok(Memory::Dense(values))
+            },
+            Backend::Paged => {
+                let mut paged = PagedMemory::from_vec(Vec::new());
+                for (start, chunk) in chunks {
+                    end(start, &chunk)?;
+                    for (offset, value) in chunk.into_iter().enumerate() {
+                        *paged.slot(start + offset, limit)? = value;
+                    }
+                }
+                paged.len = paged.len.max(len);
+                Ok(Memory::Paged(paged))
+            }
+        }
+    }
+
+    // Converting to paged memory can't go over the limit, since the
+    // values all fit already.
+    pub(super) fn make_paged(&mut self) {
+        if let Memory::Dense(values) = self {
+            *self = Memory::Paged(PagedMemory::from_vec(std::mem::take(values)));
+        }
+    }
+
+    pub(super) fn convert(&self, backend: Backend, limit: Option<usize>)
+                          -> Result<Memory<V>, OperationalError> {
+        match backend {
+            Backend::Dense => {
+                if let Some(limit) = limit.filter(|limit| self.len() > *limit) {
+                    return Err(OperationalError::MemoryLimitExceeded {
+                        address: self.len() - 1,
+                        limit
+                    });
+                }
+                Ok(Memory::Dense(self.to_vec()))
+            },
+            Backend::Paged => Ok(Memory::Paged(PagedMemory::from_vec(self.to_vec())))
+        }
+    }
+}
+
+impl<V> std::ops::Index<Address> for Memory<V> {
+    type Output = V;
+
+    fn index(&self, address: Address) -> &V {
+        match self {
+            Memory::Dense(values) => &values[address],
+            Memory::Paged(paged) => {
+                assert!(address < paged.len, "address {} is past the end of memory", address);
+                paged.pages.get(&(address / PAGE_SIZE))
+                    .map_or(&paged.zero, |page| &page[address % PAGE_SIZE])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Machine, MachineState};
+
+    #[test]
+    fn paged_memory_allows_high_addresses() -> Result<(), OperationalError> {
+        // Writes 7 to address 10^12 and outputs it.
+        let mut machine = Machine::from_slots(vec![
+            1101, 3, 4, 1_000_000_000_000, 4, 1_000_000_000_000, 99
+        ]);
+        machine.set_memory_backend(Backend::Paged)?;
+        machine.run()?;
+
+        assert_eq!(vec![7], machine.read());
+        assert_eq!(MachineState::Halted, machine.state());
+        assert_eq!(2 * PAGE_SIZE, machine.memory().allocated());
+        assert_eq!(1_000_000_000_001, machine.memory().len());
+        assert_eq!(Some(&7), machine.memory().get(1_000_000_000_000));
+        assert_eq!(None, machine.memory().get(5000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unused_pages_read_as_zero() -> Result<(), OperationalError> {
+        // Writes to page 2, leaving page 1 unallocated in between.
+        let mut machine = Machine::from_slots(vec![1101, 3, 4, 600, 99]);
+        machine.set_memory_backend(Backend::Paged)?;
+        machine.run()?;
+        assert_eq!(2 * PAGE_SIZE, machine.memory().allocated());
+
+        assert_eq!(0, machine.memory()[300]);
+        assert_eq!(7, machine.memory()[600]);
+
+        // Reading doesn't allocate anything or make memory any longer.
+        assert_eq!(&0, machine.get(300)?);
+        assert_eq!(&0, machine.get(5000)?);
+        assert_eq!(2 * PAGE_SIZE, machine.memory().allocated());
+        assert_eq!(601, machine.memory().len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn analysing_sparse_memory() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1101, 3, 4, 1_000_000_000_000, 99]);
+        machine.set_memory_backend(Backend::Paged)?;
+        machine.run()?;
+
+        // None of these should try to fill in the 10^12 addresses of nothing
+        // between the program and what it wrote. They only see the page the
+        // program's on, which is two instructions and 251 words of DATA.
+        assert_eq!(PAGE_SIZE, machine.analysed_memory().len());
+        assert_eq!(253, machine.disassemble().len());
+        assert_eq!(1, machine.control_flow_graph().blocks().count());
+        assert!(!machine.decompile().is_empty());
+        assert_eq!(1_000_000_000_001, machine.memory().len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn memory_limit() {
+        let program = vec![1101, 3, 4, 1_000_000_000_000, 99];
+
+        let error = Machine::from_slots(program.clone()).run().unwrap_err();
+        assert!(matches!(
+            error.cause(),
+            OperationalError::MemoryLimitExceeded { address: 1_000_000_000_000, limit: DEFAULT_MEMORY_LIMIT }
+        ));
+
+        let mut machine = Machine::from_slots(program);
+        machine.set_memory_backend(Backend::Paged).unwrap();
+        machine.set_memory_limit(Some(PAGE_SIZE));
+        assert!(matches!(
+            machine.run().unwrap_err().cause(),
+            OperationalError::MemoryLimitExceeded { limit: PAGE_SIZE, .. }
+        ));
+
+        let mut small = Machine::from_slots(vec![1101, 3, 4, 20, 99]);
+        small.set_memory_limit(Some(20));
+        assert!(small.run().is_err());
+        small.set_memory_limit(Some(21));
+        assert!(small.run().is_ok());
+    }
+
+    #[test]
+    fn forks_share_pages() -> Result<(), OperationalError> {
+        // Counts at address 600 for as long as its input isn't zero. The
+        // counter is two pages past the code, and nothing touches the page
+        // in between.
+        let mut machine = Machine::from_slots(vec![3, 599, 1001, 600, 1, 600, 1005, 599, 0, 99]);
+        machine.write(1);
+        machine.run()?;
+
+        let mut fork = machine.fork();
+        assert_eq!(Backend::Paged, machine.memory().backend());
+        let shared = |a: &Machine, b: &Machine| match (a.memory(), b.memory()) {
+            (Memory::Paged(a), Memory::Paged(b)) => a.pages.iter()
+                .filter(|(number, page)| Arc::ptr_eq(page, &b.pages[number]))
+                .count(),
+            _ => 0
+        };
+        assert_eq!(2, shared(&machine, &fork));
+
+        // Only the page with the counter on it stops being shared.
+        fork.write(0);
+        fork.run()?;
+        assert_eq!(1, shared(&machine, &fork));
+        assert_eq!(MachineState::Halted, fork.state());
+        assert_eq!(MachineState::Blocked, machine.state());
+        assert_eq!(&2, fork.get(600)?);
+        assert_eq!(&1, machine.get(600)?);
+
+        machine.write(0);
+        machine.run()?;
+        assert_eq!(fork.memory().to_vec(), machine.memory().to_vec());
+
+        Ok(())
+    }
+
+    #[test]
+    fn converting_backends() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1101, 3, 4, 3000, 99]);
+        machine.set_memory_backend(Backend::Paged)?;
+        machine.run()?;
+        assert_eq!(Backend::Paged, machine.memory().backend());
+
+        let paged = machine.memory().to_vec();
+        assert_eq!(3001, paged.len());
+        assert_eq!(7, paged[3000]);
+
+        machine.set_memory_backend(Backend::Dense)?;
+        assert_eq!(paged, machine.memory().to_vec());
+        assert_eq!(&7, machine.get(3000)?);
+
+        machine.set_memory_limit(Some(100));
+        assert!(machine.set_memory_backend(Backend::Paged).is_ok());
+        assert!(machine.set_memory_backend(Backend::Dense).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1647 @@
+use thiserror::Error;
+
+pub mod disassembler;
+pub mod assembler;
+pub mod debugger;
+pub mod breakpoints;
+pub mod trace;
+pub mod snapshot;
+pub mod number;
+pub mod memory;
+pub mod devices;
+pub mod ascii;
+pub mod network;
+pub mod topology;
+pub mod loops;
+pub mod profile;
+pub mod history;
+pub mod control_flow;
+pub mod decompiler;
+pub mod transpiler;
+pub mod transpiled;
+pub mod symbolic;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+use breakpoints::{Breakpoint, BreakpointId, Breakpoints};
+use trace::{Fault, TraceEvent, TraceSink, Tracer};
+use number::{Number, Overflow};
+use memory::{Backend, Memory, DEFAULT_MEMORY_LIMIT};
+use devices::{InputDevice, OutputDevice, SharedInput, SharedOutput};
+use loops::LoopDetector;
+use profile::Profile;
+use history::History;
+
+pub type Value = isize;
+pub type Address = usize;
+
+// How many instructions run goes between looking at the clock when there's
+// a timeout.
+const TIMEOUT_CHECK_INTERVAL: usize = 1024;
+
+#[derive(Debug, Error)]
+pub enum OperationalError {
+    #[error("`{0}` is not a known opcode.")]
+    InvalidOpcode(Value),
+    #[error("`{0}` is not a known parameter mode.")]
+    InvalidParameterMode(Value),
+    #[error("Index {0} is outside this machine's memory.")]
+    OutOfRange(Address),
+    #[error("Index {0} is negative.")]
+    NegativeAddress(Value),
+    #[error("Instruction {0} is negative.")]
+    NegativeInstruction(Value),
+    #[error("Instruction has too many parameter mode digits for operation: {0}.")]
+    TooManyParameterModes(Value),
+    #[error("Cannot use immediate mode parameter as address to set value.")]
+    ImmediateModeStorage,
+    #[error("Impossible set of mode digits: `{0}`.")]
+    InvalidModeDigits(Value),
+    #[error("`{0}` is too big to be an address or instruction.")]
+    Unrepresentable(String),
+    // Operands are widened to i128 so they fit whatever the machine's value
+    // type is. Only fixed width types can overflow and none are wider.
+    #[error("{opcode} of {left} and {right} at address {pointer} overflowed.")]
+    ArithmeticOverflow { opcode: Opcode, left: i128, right: i128, pointer: Address },
+    #[error("Relative base {relative_base} plus {offset} overflowed.")]
+    RelativeAddressOverflow { relative_base: i128, offset: i128 },
+    #[error("Using address {address} would take more than the memory limit of {limit} values.")]
+    MemoryLimitExceeded { address: Address, limit: usize },
+    #[error("{error} ({context})")]
+    AtInstruction { error: Box<OperationalError>, context: ErrorContext }
+}
+
+impl OperationalError {
+    // Where the machine was when this happened, if it happened while
+    // executing an instruction rather than in a direct get or set.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            OperationalError::AtInstruction { context, .. } => Some(context),
+            _ => None
+        }
+    }
+
+    // The error without any context attached.
+    pub fn cause(&self) -> &OperationalError {
+        match self {
+            OperationalError::AtInstruction { error, .. } => error.cause(),
+            _ => self
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    pub pointer: Address,
+    pub instruction: Value,
+    pub relative_base: Value,
+    pub instruction_count: usize
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "at address {} executing {} with relative base {} after {} instructions",
+               self.pointer, self.instruction, self.relative_base, self.instruction_count)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("Intcode programs must be integers, not `{token}` (token {index}, byte {offset}).")]
+    NotAnInteger { token: String, index: usize, offset: usize },
+    #[error("Invalid snapshot: {0}")]
+    InvalidSnapshot(#[from] snapshot::SnapshotError)
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Parse(#[from] ParseError),
+    #[error("{0}")]
+    Assembly(#[from] assembler::AssemblyError)
+}
+
+// The arguments every binary that works on one program takes to find it.
+// Flatten it into the binary's own arguments.
+#[derive(Debug, StructOpt)]
+pub struct ProgramArgs {
+    /// Intcode program to load, comma separated or a snapshot unless
+    /// --assembly is given.
+    #[structopt(parse(from_os_str))]
+    pub program: PathBuf,
+
+    /// Treat the program as assembly source rather than raw Intcode.
+    #[structopt(short = "a", long = "assembly")]
+    pub assembly: bool
+}
+
+impl ProgramArgs {
+    pub fn load(&self) -> Result<Machine, LoadError> {
+        load(&self.program, self.assembly)
+    }
+}
+
+// Reads a program from a file, which can be assembly source or anything
+// Machine::from_str understands.
+pub fn load(path: &Path, assembly: bool) -> Result<Machine, LoadError> {
+    let source = fs::read_to_string(path)?;
+
+    if assembly {
+        Ok(Machine::from_assembly(&source)?)
+    } else {
+        Ok(Machine::from_str(source.trim())?)
+    }
+}
+
+pub trait IntoAddress {
+    fn into_addr(self) -> Result<Address, OperationalError>;
+}
+
+impl IntoAddress for Address {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        Ok(self)
+    }
+}
+
+impl IntoAddress for Value {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        if self < 0 {
+            Err(OperationalError::NegativeAddress(self))
+        } else {
+            Ok(self as Address)
+        }
+    }
+}
+
+// Not sure why I need this but the compiler is angry at me when I try to use
+// constants...
+impl IntoAddress for i32 {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        if self < 0 {
+            Err(OperationalError::NegativeAddress(self as Value))
+        } else {
+            Ok(self as Address)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Multiply,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    RelativeBaseOffset,
+    Halt
+}
+
+impl Opcode {
+    fn from_int(i: Value) -> Result<Self, OperationalError> {
+        match i {
+            1 => Ok(Opcode::Add),
+            2 => Ok(Opcode::Multiply),
+            3 => Ok(Opcode::Input),
+            4 => Ok(Opcode::Output),
+            5 => Ok(Opcode::JumpIfTrue),
+            6 => Ok(Opcode::JumpIfFalse),
+            7 => Ok(Opcode::LessThan),
+            8 => Ok(Opcode::Equals),
+            9 => Ok(Opcode::RelativeBaseOffset),
+            99 => Ok(Opcode::Halt),
+            _ => Err(OperationalError::InvalidOpcode(i))
+        }
+    }
+
+    fn parameter_count(&self) -> usize {
+        match self {
+            Opcode::Add => 3,
+            Opcode::Multiply => 3,
+            Opcode::Input => 1,
+            Opcode::Output => 1,
+            Opcode::JumpIfTrue => 2,
+            Opcode::JumpIfFalse => 2,
+            Opcode::LessThan => 3,
+            Opcode::Equals => 3,
+            Opcode::RelativeBaseOffset => 1,
+            Opcode::Halt => 0
+        }
+    }
+
+    pub fn as_int(&self) -> Value {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Multiply => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::RelativeBaseOffset => 9,
+            Opcode::Halt => 99
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str) -> Option<Self> {
+        match mnemonic.to_uppercase().as_str() {
+            "ADD" => Some(Opcode::Add),
+            "MUL" => Some(Opcode::Multiply),
+            "IN" => Some(Opcode::Input),
+            "OUT" => Some(Opcode::Output),
+            "JT" => Some(Opcode::JumpIfTrue),
+            "JF" => Some(Opcode::JumpIfFalse),
+            "LT" => Some(Opcode::LessThan),
+            "EQ" => Some(Opcode::Equals),
+            "RBO" => Some(Opcode::RelativeBaseOffset),
+            "HLT" => Some(Opcode::Halt),
+            _ => None
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Multiply => "MUL",
+            Opcode::Input => "IN",
+            Opcode::Output => "OUT",
+            Opcode::JumpIfTrue => "JT",
+            Opcode::JumpIfFalse => "JF",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::RelativeBaseOffset => "RBO",
+            Opcode::Halt => "HLT"
+        }
+    }
+
+    // Whether the last parameter of this opcode is an address the instruction
+    // writes to, and so can't be in immediate mode.
+    fn writes_last_parameter(&self) -> bool {
+        matches!(self, Opcode::Add | Opcode::Multiply | Opcode::Input |
+                       Opcode::LessThan | Opcode::Equals)
+    }
+}
+
+impl std::fmt::Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.pad(self.mnemonic())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParameterMode {
+    Positional,
+    Immediate,
+    Relative
+}
+
+impl ParameterMode {
+    fn from_int(value: Value) -> Result<Self, OperationalError> {
+        match value {
+            0 => Ok(ParameterMode::Positional),
+            1 => Ok(ParameterMode::Immediate),
+            2 => Ok(ParameterMode::Relative),
+            _ => Err(OperationalError::InvalidParameterMode(value))
+        }
+    }
+
+    pub fn as_int(&self) -> Value {
+        match self {
+            ParameterMode::Positional => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Parameter<V = Value> {
+    pub value: V,
+    pub mode: ParameterMode
+}
+
+impl<V: Number> std::fmt::Display for Parameter<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.mode {
+            ParameterMode::Positional => write!(f, "[{}]", self.value),
+            ParameterMode::Immediate => write!(f, "#{}", self.value),
+            ParameterMode::Relative if self.value.is_negative() => write!(f, "rb{}", self.value),
+            ParameterMode::Relative => write!(f, "rb+{}", self.value)
+        }
+    }
+}
+
+// Filler for the unused parameter slots of instructions with fewer than
+// three parameters.
+fn no_parameters<V: Number>() -> [Parameter<V>; 3] {
+    let filler = || Parameter { value: V::default(), mode: ParameterMode::Positional };
+    [filler(), filler(), filler()]
+}
+
+// No instruction has more than three parameters, so they're stored inline
+// rather than in a Vec. That keeps decoding allocation free and lets
+// instructions be Copy, which matters since every instruction executed goes
+// through one. They're only Copy for Copy values though, so a BigInt machine
+// pays for a clone per instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Instruction<V = Value> {
+    pub opcode: Opcode,
+    parameters: [Parameter<V>; 3]
+}
+
+impl<V: Number> std::fmt::Display for Instruction<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.parameters().is_empty() {
+            return write!(f, "{}", self.opcode);
+        }
+
+        write!(f, "{:<4}", self.opcode)?;
+        for (i, parameter) in self.parameters().iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {}", parameter)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: Number> Instruction<V> {
+    // Panics if given the wrong number of parameters for the opcode.
+    pub fn new(opcode: Opcode, parameters: &[Parameter<V>]) -> Self {
+        assert_eq!(opcode.parameter_count(), parameters.len(),
+                   "{} takes {} parameters", opcode, opcode.parameter_count());
+
+        let mut fixed = no_parameters();
+        fixed[..parameters.len()].clone_from_slice(parameters);
+        Instruction { opcode, parameters: fixed }
+    }
+
+    pub fn parameters(&self) -> &[Parameter<V>] {
+        &self.parameters[..self.opcode.parameter_count()]
+    }
+
+    // The instruction word itself, without the parameters.
+    pub fn encode(&self) -> Value {
+        let mut word = self.opcode.as_int();
+        let mut place = 100;
+        for parameter in self.parameters() {
+            word += parameter.mode.as_int() * place;
+            place *= 10;
+        }
+        word
+    }
+
+    // Decodes an instruction from its first word, fetching parameters from
+    // wherever the caller keeps them.
+    fn decode<F>(value: &V, mut parameter_value: F) -> Result<Self, OperationalError>
+    where F: FnMut(usize) -> Result<V, OperationalError> {
+        let word = value.to_value()
+            .ok_or_else(|| OperationalError::Unrepresentable(value.to_string()))?;
+        let (opcode, modes) = Instruction::op_and_mode_digits(&word)?;
+
+        let mut parameters = no_parameters();
+        for i in 0..opcode.parameter_count() {
+            parameters[i] = Parameter { value: parameter_value(i)?, mode: modes[i] };
+        }
+
+        Ok(Instruction { opcode, parameters })
+    }
+}
+
+// Instruction words always fit in a Value, whatever the machine computes with.
+impl Instruction {
+    fn op_and_mode_digits(value: &Value) -> Result<(Opcode, [ParameterMode; 3]), OperationalError> {
+        if value < &0 {
+            return Err(OperationalError::NegativeInstruction(*value));
+        }
+
+        let opcode = Opcode::from_int(*value % 100)?;
+
+        // The mode digits are read right to left, see problem description.
+        let mode_int = *value / 100;
+        if mode_int > 999 {
+            return Err(OperationalError::InvalidModeDigits(mode_int));
+        }
+
+        let mut modes = [ParameterMode::Positional; 3];
+        let mut remaining = mode_int;
+        for mode in modes.iter_mut() {
+            *mode = ParameterMode::from_int(remaining % 10)
+                .map_err(|_| OperationalError::InvalidModeDigits(mode_int))?;
+            remaining /= 10;
+        }
+
+        if mode_int >= (10 as Value).pow(opcode.parameter_count() as u32) {
+            return Err(OperationalError::TooManyParameterModes(*value));
+        }
+
+        Ok((opcode, modes))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MachineState {
+    Running,
+    Halted, // Halted means the machine has executed a Halt (99) instruction,
+    Blocked // while Blocked means it wants to execute an Input but has no input
+}
+
+impl std::fmt::Display for MachineState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            MachineState::Running => "Running",
+            MachineState::Halted => "Halted",
+            MachineState::Blocked => "Blocked"
+        })
+    }
+}
+
+// What a single call to Machine::step did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<V = Value> {
+    Executed { address: Address, instruction: Instruction<V> },
+    ConsumedInput { address: Address, instruction: Instruction<V>, value: V },
+    ProducedOutput { address: Address, instruction: Instruction<V>, value: V },
+    // Blocked means nothing was executed because there's no input to read,
+    Blocked,
+    // while Halted covers both executing a Halt and stepping a halted machine.
+    Halted
+}
+
+// Why a call to one of the run methods returned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    Halted,
+    Blocked,
+    BudgetExhausted,
+    Breakpoint(BreakpointId),
+    // Only with loop detection on. The machine got back to a state it was
+    // already in at address, period instructions ago, without any I/O in
+    // between, so it's never going to do anything else.
+    InfiniteLoop { address: Address, period: usize },
+    // Only with a timeout set.
+    TimedOut
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<V = Value> {
+    slots: Memory<V>,
+    memory_limit: Option<usize>,
+    pointer: Address,
+    state: MachineState,
+    relative_base: V,
+
+    input_pointer: Address,
+    input: Vec<V>,
+
+    output_pointer: Address,
+    output: Vec<V>,
+
+    // Instructions are decoded once per address and reused until something
+    // writes over one of their words. Forks share this until they change it.
+    decoded: Arc<Vec<Option<Instruction<V>>>>,
+
+    instruction_counter: usize,
+
+    breakpoints: Breakpoints<V>,
+    // The first read or write watchpoint hit by the current instruction.
+    triggered: Option<BreakpointId>,
+    // Where we last stopped for an Execute breakpoint, so that resuming
+    // doesn't immediately stop there again.
+    resume_address: Option<Address>,
+
+    tracer: Option<Tracer<V>>,
+    input_device: Option<SharedInput<V>>,
+    output_device: Option<SharedOutput<V>>,
+    // What the current instruction has read and written so far, only kept
+    // up to date while tracing.
+    trace_values: Vec<V>,
+    trace_write: Option<(Address, V)>,
+
+    overflow: Overflow,
+
+    loop_detector: Option<LoopDetector>,
+    timeout: Option<Duration>,
+    profile: Option<Profile>,
+    history: Option<History<V>>
+}
+
+// Machines over Value get an inherent from_str so that Machine::from_str
+// doesn't need a type annotation everywhere. Everything else goes through
+// this, as in input.parse::<Machine<i128>>().
+impl Machine {
+    fn from_slots(slots: Vec<Value>) -> Machine {
+        Machine::with_slots(slots)
+    }
+
+    pub fn from_str(input: &str) -> Result<Machine, ParseError> {
+        input.parse()
+    }
+}
+
+impl<V: Number> std::str::FromStr for Machine<V> {
+    type Err = ParseError;
+
+    // Accepts either a comma separated program or a snapshot, so that
+    // anything that loads a program can also resume a saved machine.
+    fn from_str(input: &str) -> Result<Machine<V>, ParseError> {
+        if snapshot::is_snapshot(input) {
+            return Ok(Machine::from_snapshot(input)?);
+        }
+
+        let tokens = input.split(',');
+        let mut slots = Vec::new();
+        let mut offset = 0;
+
+        for (index, token) in tokens.enumerate() {
+            slots.push(match token.trim().parse::<V>() {
+                Ok(code) => code,
+                Err(_) => return Err(ParseError::NotAnInteger {
+                    token: token.trim().to_string(),
+                    index,
+                    offset: offset + token.len() - token.trim_start().len()
+                })
+            });
+            // +1 for the comma.
+            offset += token.len() + 1;
+        }
+
+        Ok(Machine::with_slots(slots))
+    }
+}
+
+impl<V: Number> Machine<V> {
+    fn with_slots(slots: Vec<V>) -> Machine<V> {
+        Machine {
+            slots: Memory::Dense(slots),
+            memory_limit: Some(DEFAULT_MEMORY_LIMIT),
+            pointer: 0,
+            state: MachineState::Running,
+            relative_base: V::default(),
+
+            input_pointer: 0,
+            input: Vec::new(),
+
+            output_pointer: 0,
+            output: Vec::new(),
+
+            decoded: Arc::new(Vec::new()),
+
+            instruction_counter: 0,
+
+            breakpoints: Breakpoints::default(),
+            triggered: None,
+            resume_address: None,
+
+            tracer: None,
+            input_device: None,
+            output_device: None,
+            trace_values: Vec::new(),
+            trace_write: None,
+
+            overflow: Overflow::Checked,
+
+            loop_detector: None,
+            timeout: None,
+            profile: None,
+            history: None
+        }
+    }
+
+    pub fn run(&mut self) -> Result<RunOutcome, OperationalError> {
+        self.run_limited(None)
+    }
+
+    // Like run, but gives up after executing max_instructions so that a
+    // program that never halts or blocks can't hang the caller.
+    pub fn run_for(&mut self, max_instructions: usize) -> Result<RunOutcome, OperationalError> {
+        self.run_limited(Some(max_instructions))
+    }
+
+    fn run_limited(&mut self, budget: Option<usize>) -> Result<RunOutcome, OperationalError> {
+        self.retry_input();
+        let resume_address = self.resume_address.take();
+        let started = Instant::now();
+        let mut executed = 0;
+
+        loop {
+            match self.state {
+                MachineState::Halted => return Ok(RunOutcome::Halted),
+                MachineState::Blocked => return Ok(RunOutcome::Blocked),
+                _ => {}
+            }
+
+            if budget == Some(executed) {
+                return Ok(RunOutcome::BudgetExhausted);
+            }
+
+            // Checking the clock on every instruction would slow everything
+            // down a lot more than it's worth.
+            if let Some(timeout) = self.timeout {
+                if executed % TIMEOUT_CHECK_INTERVAL == 0 && started.elapsed() >= timeout {
+                    return Ok(RunOutcome::TimedOut);
+                }
+            }
+
+            if !self.breakpoints.is_empty() {
+                let resuming = executed == 0 && resume_address == Some(self.pointer);
+                if let Some(id) = self.breakpoints.executing(self.pointer).filter(|_| !resuming) {
+                    self.resume_address = Some(self.pointer);
+                    return Ok(RunOutcome::Breakpoint(id));
+                }
+            }
+
+            let address = self.pointer;
+            let instruction = self.execute_next()?;
+            executed += 1;
+
+            if self.loop_detector.is_some() {
+                if let Some(period) = self.check_for_loop(address, &instruction) {
+                    return Ok(RunOutcome::InfiniteLoop { address: self.pointer, period });
+                }
+            }
+
+            if !self.breakpoints.is_empty() && self.state == MachineState::Running {
+                if let Some(id) = self.triggered.take()
+                    .or_else(|| self.breakpoints.condition_met(self)) {
+                    return Ok(RunOutcome::Breakpoint(id));
+                }
+            }
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint<V>) -> BreakpointId {
+        self.breakpoints.add(breakpoint)
+    }
+
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> Option<Breakpoint<V>> {
+        self.breakpoints.remove(id)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> &Breakpoints<V> {
+        &self.breakpoints
+    }
+
+    // Executes the single instruction at the pointer and reports what it did.
+    // Stepping a halted machine does nothing, and stepping a blocked machine
+    // leaves it blocked.
+    pub fn step(&mut self) -> Result<Step<V>, OperationalError> {
+        if self.state == MachineState::Halted {
+            return Ok(Step::Halted);
+        }
+        self.retry_input();
+
+        let address = self.pointer;
+        let input_pointer = self.input_pointer;
+        let output_length = self.output.len();
+
+        let instruction = self.execute_next()?;
+
+        Ok(match self.state {
+            MachineState::Halted => Step::Halted,
+            MachineState::Blocked => Step::Blocked,
+            MachineState::Running if self.input_pointer > input_pointer => {
+                let value = self.input[input_pointer].clone();
+                Step::ConsumedInput { address, instruction, value }
+            },
+            MachineState::Running if self.output.len() > output_length => {
+                let value = self.output[output_length].clone();
+                Step::ProducedOutput { address, instruction, value }
+            },
+            MachineState::Running => Step::Executed { address, instruction }
+        })
+    }
+
+    // The one place instructions get executed outside of tests, so that
+    // watchpoints and tracing see everything.
+    fn execute_next(&mut self) -> Result<Instruction<V>, OperationalError> {
+        let address = self.pointer;
+        let undo = self.begin_undo();
+
+        // Nothing an instruction does before failing changes the pointer,
+        // relative base or counter, so they can all be read after the fact.
+        let instruction = self.execute_traced().map_err(|error| OperationalError::AtInstruction {
+            error: Box::new(error),
+            context: ErrorContext {
+                pointer: address,
+                instruction: self.slots.get(address).map_or(0, Number::saturating_value),
+                relative_base: self.relative_base.saturating_value(),
+                instruction_count: self.instruction_counter
+            }
+        })?;
+
+        if let Some(undo) = undo {
+            self.finish_undo(undo);
+        }
+        Ok(instruction)
+    }
+
+    fn execute_traced(&mut self) -> Result<Instruction<V>, OperationalError> {
+        self.triggered = None;
+        let address = self.pointer;
+        let counter = self.instruction_counter;
+
+        let instruction = self.read_instruction()
+            .map_err(|error| self.trace_fault(counter, address, None, error))?;
+
+        if self.tracer.is_none() {
+            self.execute_instruction(&instruction)?;
+            self.profile_execution(address, &instruction);
+            return Ok(instruction);
+        }
+
+        let relative_base = self.relative_base.clone();
+        self.trace_values.clear();
+        self.trace_write = None;
+        self.execute_instruction(&instruction)
+            .map_err(|error| self.trace_fault(counter, address, Some(instruction.clone()), error))?;
+        self.profile_execution(address, &instruction);
+
+        // A blocked Input didn't actually do anything, so it isn't traced.
+        if self.state != MachineState::Blocked {
+            if let Some(tracer) = &self.tracer {
+                tracer.record(&TraceEvent {
+                    counter,
+                    address,
+                    instruction: instruction.clone(),
+                    values: self.trace_values.clone(),
+                    write: self.trace_write.clone(),
+                    relative_base: if self.relative_base != relative_base {
+                        Some(self.relative_base.clone())
+                    } else {
+                        None
+                    }
+                });
+            }
+        }
+
+        Ok(instruction)
+    }
+
+    // Passes the error through, so it can go in a map_err.
+    fn trace_fault(&self, counter: usize, address: Address, instruction: Option<Instruction<V>>,
+                   error: OperationalError) -> OperationalError {
+        if let Some(tracer) = &self.tracer {
+            tracer.fault(&Fault { counter, address, instruction, error: error.to_string() });
+        }
+        error
+    }
+
+    fn profile_execution(&mut self, address: Address, instruction: &Instruction<V>) {
+        if let Some(profile) = &mut self.profile {
+            // Same as tracing, a blocked Input doesn't count.
+            if self.state != MachineState::Blocked {
+                profile.executed(address, instruction.opcode);
+            }
+        }
+    }
+
+    pub fn trace_to<S>(&mut self, sink: Arc<Mutex<S>>)
+    where S: TraceSink<V> + 'static {
+        self.tracer = Some(Tracer(sink));
+    }
+
+    pub fn stop_tracing(&mut self) {
+        self.tracer = None;
+    }
+
+    pub fn input_from<D>(&mut self, device: Arc<Mutex<D>>)
+    where D: InputDevice<V> + 'static {
+        self.input_device = Some(SharedInput(device));
+    }
+
+    // Anything that hasn't been read yet goes to the device straight away.
+    pub fn output_to<D>(&mut self, device: Arc<Mutex<D>>)
+    where D: OutputDevice<V> + 'static {
+        let device = SharedOutput(device);
+        for value in self.read() {
+            device.output(value);
+        }
+        self.output_device = Some(device);
+    }
+
+    pub fn disconnect_devices(&mut self) {
+        self.input_device = None;
+        self.output_device = None;
+    }
+
+    // A machine that blocked on an input device might have something to
+    // read now, and there's no write to unblock it, so it gets to try again.
+    fn retry_input(&mut self) {
+        if self.state == MachineState::Blocked && self.input_device.is_some() {
+            self.state = MachineState::Running;
+        }
+    }
+
+    pub fn get<I>(&mut self, index: I) -> Result<&V, OperationalError>
+    where I: IntoAddress {
+        let addr = index.into_addr()?;
+        self.slots.read(addr, self.memory_limit)
+    }
+
+    pub fn set<I>(&mut self, index: I, new_value: V) -> Result<(), OperationalError>
+    where I: IntoAddress {
+        let addr = index.into_addr()?;
+        let slot = self.slots.slot(addr, self.memory_limit)?;
+
+        // Every cached instruction that this address is part of is stale now.
+        // Instructions are at most four words long, so that's at most the
+        // four starting at or just before it.
+        let stale_start = addr.saturating_sub(3).min(self.decoded.len());
+        let stale_end = (addr + 1).min(self.decoded.len());
+        // Checking first saves a fork from copying the cache over a write to
+        // plain data.
+        if self.decoded[stale_start..stale_end].iter().any(Option::is_some) {
+            let decoded = Arc::make_mut(&mut self.decoded);
+            for cached in &mut decoded[stale_start..stale_end] {
+                *cached = None;
+            }
+        }
+
+        *slot = new_value;
+        Ok(())
+    }
+
+    fn get_parameter_val(&mut self, parameter: &Parameter<V>) -> Result<V, OperationalError> {
+        let addr = match parameter.mode {
+            ParameterMode::Positional => parameter.value.clone().into_addr()?,
+            ParameterMode::Immediate => {
+                if self.tracer.is_some() {
+                    self.trace_values.push(parameter.value.clone());
+                }
+                return Ok(parameter.value.clone());
+            },
+            ParameterMode::Relative => self.relative_address(parameter)?
+        };
+
+        if !self.breakpoints.is_empty() && self.triggered.is_none() {
+            self.triggered = self.breakpoints.reading(addr);
+        }
+
+        if let Some(profile) = &mut self.profile {
+            profile.read(addr);
+        }
+
+        let value = self.get(addr)?.clone();
+        if self.tracer.is_some() {
+            self.trace_values.push(value.clone());
+        }
+        Ok(value)
+    }
+
+    // Always checked, whatever the machine's overflow setting. Wrapping
+    // around to some other address is never what a program meant.
+    fn relative_address(&self, parameter: &Parameter<V>) -> Result<Address, OperationalError> {
+        self.relative_base.add_with(&parameter.value, Overflow::Checked)
+            .ok_or_else(|| OperationalError::RelativeAddressOverflow {
+                relative_base: self.relative_base.to_i128().unwrap_or_default(),
+                offset: parameter.value.to_i128().unwrap_or_default()
+            })?
+            .into_addr()
+    }
+
+    fn set_at_parameter(&mut self, parameter: &Parameter<V>, value: V) -> Result<(), OperationalError> {
+        let addr = match parameter.mode {
+            ParameterMode::Positional => parameter.value.clone().into_addr()?,
+            ParameterMode::Relative => self.relative_address(parameter)?,
+            ParameterMode::Immediate => return Err(OperationalError::ImmediateModeStorage)
+        };
+
+        if !self.breakpoints.is_empty() && self.triggered.is_none() {
+            self.triggered = self.breakpoints.writing(addr);
+        }
+        if self.tracer.is_some() {
+            self.trace_write = Some((addr, value.clone()));
+        }
+        if let Some(profile) = &mut self.profile {
+            profile.wrote(addr);
+        }
+        if let Some(history) = &mut self.history {
+            history.wrote(addr, self.slots.get(addr).cloned().unwrap_or_default());
+        }
+
+        self.set(addr, value)
+    }
+
+    fn read_instruction(&mut self) -> Result<Instruction<V>, OperationalError> {
+        let pointer = self.pointer;
+        if let Some(Some(instruction)) = self.decoded.get(pointer) {
+            return Ok(instruction.clone());
+        }
+
+        let value = self.get(pointer)?.clone();
+        let instruction = Instruction::decode(&value, |i| Ok(self.get(pointer + i + 1)?.clone()))?;
+
+        // Only allocated memory is cached, so that running code up at some
+        // huge address in paged memory doesn't need a huge cache.
+        if pointer < self.slots.allocated() {
+            let allocated = self.slots.allocated();
+            let decoded = Arc::make_mut(&mut self.decoded);
+            if pointer >= decoded.len() {
+                decoded.resize(allocated, None);
+            }
+            decoded[pointer] = Some(instruction.clone());
+        }
+
+        Ok(instruction)
+    }
+
+    fn execute_instruction(&mut self, instruction: &Instruction<V>) -> Result<(), OperationalError> {
+        if self.state != MachineState::Running {
+            return Ok(());
+        }
+
+        let mut advance_pointer = true;
+        // Assuming in this block that we have the right number of parameters
+        // in our instructions because of how we construct them in
+        // read_instruction.
+        match instruction.opcode {
+            Opcode::Halt => {
+                self.state = MachineState::Halted;
+            },
+            Opcode::Add => {
+                let left = self.get_parameter_val(&instruction.parameters[0])?;
+                let right = self.get_parameter_val(&instruction.parameters[1])?;
+
+                let sum = self.arithmetic(Opcode::Add, &left, &right)?;
+                self.set_at_parameter(&instruction.parameters[2], sum)?;
+            },
+            Opcode::Multiply => {
+                let left = self.get_parameter_val(&instruction.parameters[0])?;
+                let right = self.get_parameter_val(&instruction.parameters[1])?;
+
+                let product = self.arithmetic(Opcode::Multiply, &left, &right)?;
+                self.set_at_parameter(&instruction.parameters[2], product)?;
+            },
+            Opcode::Input => {
+                if self.input_pointer >= self.input.len() {
+                    // Keeping what the device gives us with the rest of the
+                    // input means step and snapshots don't need to care where
+                    // it came from.
+                    if let Some(value) = self.input_device.as_ref().and_then(SharedInput::next_input) {
+                        self.input.push(value);
+                    }
+                }
+
+                match self.input.get(self.input_pointer) {
+                    None => {
+                        self.state = MachineState::Blocked;
+                        advance_pointer = false;
+                    },
+                    Some(_) => {
+                        // This is dumb, but it's my best guess of how to get
+                        // around the borrow reservation conflict thing.
+                        let val = self.input.get(self.input_pointer).unwrap().clone();
+                        self.set_at_parameter(&instruction.parameters[0], val)?;
+                        self.input_pointer += 1;
+                    }
+                }
+            },
+            Opcode::Output => {
+                let val = self.get_parameter_val(&instruction.parameters[0])?;
+
+                if let Some(device) = &self.output_device {
+                    device.output(val.clone());
+                }
+                self.output.push(val);
+                if self.output_device.is_some() {
+                    // Already delivered, so read shouldn't hand it out again.
+                    self.output_pointer = self.output.len();
+                }
+            },
+            Opcode::JumpIfTrue => {
+                let val = self.get_parameter_val(&instruction.parameters[0])?;
+                if !val.is_zero() {
+                    self.pointer = self.get_parameter_val(&instruction.parameters[1])?.into_addr()?;
+                    advance_pointer = false;
+                }
+            },
+            Opcode::JumpIfFalse => {
+                let val = self.get_parameter_val(&instruction.parameters[0])?;
+                if val.is_zero() {
+                    self.pointer = self.get_parameter_val(&instruction.parameters[1])?.into_addr()?;
+                    advance_pointer = false;
+                }
+            },
+            Opcode::LessThan => {
+                let left = self.get_parameter_val(&instruction.parameters[0])?;
+                let right = self.get_parameter_val(&instruction.parameters[1])?;
+
+                let value = V::from_value(if left < right { 1 } else { 0 });
+                self.set_at_parameter(&instruction.parameters[2], value)?;
+            },
+            Opcode::Equals => {
+                let left = self.get_parameter_val(&instruction.parameters[0])?;
+                let right = self.get_parameter_val(&instruction.parameters[1])?;
+
+                let value = V::from_value(if left == right { 1 } else { 0 });
+                self.set_at_parameter(&instruction.parameters[2], value)?;
+            },
+            Opcode::RelativeBaseOffset => {
+                let value = self.get_parameter_val(&instruction.parameters[0])?;
+                self.relative_base = self.arithmetic(Opcode::RelativeBaseOffset,
+                                                     &self.relative_base, &value)?;
+            }
+        }
+
+        if advance_pointer {
+            // +1 for the instruction itself.
+            self.pointer += instruction.opcode.parameter_count() + 1;
+        }
+
+        self.instruction_counter += 1;
+
+        Ok(())
+    }
+
+    // Multiply multiplies, everything else adds.
+    fn arithmetic(&self, opcode: Opcode, left: &V, right: &V) -> Result<V, OperationalError> {
+        let result = match opcode {
+            Opcode::Multiply => left.mul_with(right, self.overflow),
+            _ => left.add_with(right, self.overflow)
+        };
+
+        result.ok_or_else(|| OperationalError::ArithmeticOverflow {
+            opcode,
+            left: left.to_i128().unwrap_or_default(),
+            right: right.to_i128().unwrap_or_default(),
+            pointer: self.pointer
+        })
+    }
+
+    // A copy of this machine that shares memory with it, a page at a time,
+    // until one of them writes to that page. Much cheaper than clone for
+    // searching over lots of machine states. Since pages are where the
+    // sharing happens, this moves this machine over to paged memory first if
+    // it isn't already.
+    pub fn fork(&mut self) -> Machine<V> {
+        self.slots.make_paged();
+        self.clone()
+    }
+
+    pub fn write(&mut self, input: V) {
+        self.input.push(input);
+
+        if self.state == MachineState::Blocked {
+            self.state = MachineState::Running;
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<V> {
+        if self.output_pointer >= self.output.len() {
+            Vec::new()
+        } else {
+            let outslice = &self.output[self.output_pointer..self.output.len()];
+            self.output_pointer = self.output.len();
+            outslice.to_vec()
+        }
+    }
+
+    pub fn peek(&self) -> usize {
+        if self.output_pointer >= self.output.len() {
+            0
+        } else {
+            self.output.len() - self.output_pointer
+        }
+    }
+
+    pub fn state(&self) -> MachineState {
+        self.state
+    }
+
+    pub fn pointer(&self) -> Address {
+        self.pointer
+    }
+
+    pub fn relative_base(&self) -> V {
+        self.relative_base.clone()
+    }
+
+    pub fn set_relative_base(&mut self, relative_base: V) {
+        self.relative_base = relative_base;
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    // How long each call to one of the run methods can go on for before
+    // giving up with RunOutcome::TimedOut. None, the default, for no limit.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn memory(&self) -> &Memory<V> {
+        &self.slots
+    }
+
+    // How far the static analyses, like disassembly and decompiling, look.
+    // That's the memory limit, or all of dense memory if there isn't one.
+    // Paged memory can use addresses way past it, but filling in all the
+    // unused space before them would take more memory than there is.
+    fn analysis_end(&self) -> Address {
+        let end = self.memory_limit.unwrap_or(match self.slots {
+            Memory::Dense(_) => self.slots.len(),
+            Memory::Paged(_) => DEFAULT_MEMORY_LIMIT
+        });
+        self.slots.used_end(end)
+    }
+
+    // Memory up to wherever the static analyses stop looking.
+    pub fn analysed_memory(&self) -> Vec<V> {
+        self.slots.to_vec_upto(self.analysis_end())
+    }
+
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    // None for no limit at all. Memory that's already allocated stays
+    // allocated even if it's over a new limit.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    // Moves everything in memory over to a new backend. Fails if that would
+    // need more memory than the limit allows, which can happen going from
+    // paged memory to dense.
+    pub fn set_memory_backend(&mut self, backend: Backend) -> Result<(), OperationalError> {
+        if backend != self.slots.backend() {
+            self.slots = self.slots.convert(backend, self.memory_limit)?;
+        }
+        Ok(())
+    }
+
+    // Input that's been written but not consumed by an Input instruction yet.
+    pub fn pending_input(&self) -> &[V] {
+        &self.input[self.input_pointer.min(self.input.len())..]
+    }
+
+    // Like read, but leaves the output unread.
+    pub fn pending_output(&self) -> &[V] {
+        &self.output[self.output_pointer.min(self.output.len())..]
+    }
+
+    // How many instructions have been executed, which is mostly interesting
+    // for debugging but also tells whether a run got anywhere at all.
+    pub fn instruction_counter(&self) -> usize {
+        self.instruction_counter
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_machine() -> Result<(), ParseError> {
+        let input = "1,9,10,3,2,3,11,0,99,30,40,50";
+        let machine = Machine::from_str(input)?;
+
+        assert_eq!(0, machine.pointer);
+        assert_eq!(MachineState::Running, machine.state);
+        assert_eq!(1, machine.slots[0]);
+        assert_eq!(9, machine.slots[1]);
+        assert_eq!(12, machine.slots.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn step() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1,9,10,3,2,3,11,0,99,30,40,50]);
+
+        let instruction = machine.read_instruction()?;
+        machine.execute_instruction(&instruction)?;
+        assert_eq!(70, machine.slots[3]);
+        assert_eq!(MachineState::Running, machine.state);
+
+        let instruction = machine.read_instruction()?;
+        machine.execute_instruction(&instruction)?;
+        assert_eq!(3500, machine.slots[0]);
+        assert_eq!(MachineState::Running, machine.state);
+
+        let instruction = machine.read_instruction()?;
+        machine.execute_instruction(&instruction)?;
+        assert_eq!(MachineState::Halted, machine.state);
+
+        Ok(())
+    }
+
+    #[test]
+    fn step_results() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![3, 7, 4, 7, 1101, 1, 1, 7, 99]);
+
+        assert_eq!(Step::Blocked, machine.step()?);
+        machine.write(5);
+        assert_eq!(
+            Step::ConsumedInput {
+                address: 0,
+                instruction: Instruction::new(Opcode::Input, &[
+                    Parameter { value: 7, mode: ParameterMode::Positional }
+                ]),
+                value: 5
+            },
+            machine.step()?
+        );
+        match machine.step()? {
+            Step::ProducedOutput { address: 2, value: 5, .. } => {},
+            other => panic!("Expected output of 5 at 2, got {:?}", other)
+        }
+        match machine.step()? {
+            Step::Executed { address: 4, instruction } => {
+                assert_eq!(Opcode::Add, instruction.opcode)
+            },
+            other => panic!("Expected an add at 4, got {:?}", other)
+        }
+        assert_eq!(Step::Halted, machine.step()?);
+        assert_eq!(Step::Halted, machine.step()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_for_budget() -> Result<(), OperationalError> {
+        // Adds one to address 7 forever.
+        let mut forever = Machine::from_slots(vec![1001, 7, 1, 7, 1105, 1, 0, 0]);
+        assert_eq!(RunOutcome::BudgetExhausted, forever.run_for(100)?);
+        assert_eq!(50, forever.slots[7]);
+        assert_eq!(RunOutcome::BudgetExhausted, forever.run_for(2)?);
+        assert_eq!(51, forever.slots[7]);
+
+        let mut blocks = Machine::from_slots(vec![3, 3, 99, 0]);
+        assert_eq!(RunOutcome::Blocked, blocks.run_for(100)?);
+        blocks.write(1);
+        assert_eq!(RunOutcome::Halted, blocks.run_for(100)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn error_context() {
+        let mut machine = Machine::from_slots(vec![109, 3, 1101, 1, 1, 7, 42, 0]);
+
+        let error = machine.run().unwrap_err();
+        assert!(matches!(error.cause(), OperationalError::InvalidOpcode(42)));
+        assert_eq!(
+            Some(&ErrorContext {
+                pointer: 6,
+                instruction: 42,
+                relative_base: 3,
+                instruction_count: 2
+            }),
+            error.context()
+        );
+        assert_eq!(
+            "`42` is not a known opcode. (at address 6 executing 42 with relative base 3 after 2 instructions)",
+            error.to_string()
+        );
+
+        assert!(machine.get(-1).unwrap_err().context().is_none());
+    }
+
+    #[test]
+    fn parse_error_position() {
+        match Machine::from_str("1,2, 3x,4") {
+            Err(ParseError::NotAnInteger { token, index, offset }) => {
+                assert_eq!("3x", token);
+                assert_eq!(2, index);
+                assert_eq!(5, offset);
+            },
+            other => panic!("Expected a parse error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn run_to_halt() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1,9,10,3,2,3,11,0,99,30,40,50]);
+
+        machine.run()?;
+        assert_eq!(3500, machine.slots[0]);
+        assert_eq!(MachineState::Halted, machine.state);
+
+        Ok(())
+    }
+
+    #[test]
+    fn op_and_modes_add() -> Result<(), OperationalError> {
+        use ParameterMode::*;
+
+        let (op, modes) = Instruction::op_and_mode_digits(&1)?;
+        assert_eq!(Opcode::Add, op);
+        assert_eq!([Positional, Positional, Positional], modes);
+
+        let (op2, modes2) = Instruction::op_and_mode_digits(&1001)?;
+        assert_eq!(Opcode::Add, op2);
+        assert_eq!([Positional, Immediate, Positional], modes2);
+
+        let (_, modes3) = Instruction::op_and_mode_digits(&21201)?;
+        assert_eq!([Relative, Immediate, Relative], modes3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn op_and_modes_halt() -> Result<(), OperationalError> {
+        let (op, modes) = Instruction::op_and_mode_digits(&99)?;
+        assert_eq!(Opcode::Halt, op);
+        assert_eq!([ParameterMode::Positional; 3], modes);
+
+        Ok(())
+    }
+
+    #[test]
+    fn op_and_modes_invalid() {
+        assert!(matches!(Instruction::op_and_mode_digits(&199),
+                         Err(OperationalError::TooManyParameterModes(199))));
+        assert!(matches!(Instruction::op_and_mode_digits(&10104),
+                         Err(OperationalError::TooManyParameterModes(10104))));
+        assert!(matches!(Instruction::op_and_mode_digits(&301),
+                         Err(OperationalError::InvalidModeDigits(3))));
+        assert!(matches!(Instruction::op_and_mode_digits(&100001),
+                         Err(OperationalError::InvalidModeDigits(1000))));
+    }
+
+    #[test]
+    fn test_output() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![4, 0, 104, 20, 99]);
+
+        machine.run()?;
+        assert_eq!(vec![4, 20], machine.output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_input() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![3, 3, 99, 0]);
+        machine.write(20);
+
+        machine.run()?;
+        assert_eq!(20, machine.slots[3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn jump_condition_large_example() -> Result<(), OperationalError> {
+        let machine = Machine::from_slots(vec![
+            3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,
+            1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,
+            999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99
+        ]);
+
+        let mut low_input = machine.clone();
+        low_input.write(4);
+        let mut exact_input = machine.clone();
+        exact_input.write(8);
+        let mut high_input = machine.clone();
+        high_input.write(12);
+
+        low_input.run()?;
+        assert_eq!(vec![999], low_input.output);
+
+        exact_input.run()?;
+        assert_eq!(vec![1000], exact_input.output);
+
+        high_input.run()?;
+        assert_eq!(vec![1001], high_input.output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn blocked() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![
+            3, 7, 3, 8, 3, 9, 99, 0, 0, 0
+        ]);
+
+        machine.run()?;
+
+        assert_eq!(MachineState::Blocked, machine.state);
+
+        machine.write(10);
+
+        assert_eq!(MachineState::Running, machine.state);
+
+        machine.run()?;
+
+        assert_eq!(MachineState::Blocked, machine.state);
+
+        machine.write(20);
+        machine.write(30);
+
+        machine.run()?;
+
+        assert_eq!(MachineState::Halted, machine.state);
+
+        machine.write(40);
+
+        assert_eq!(MachineState::Halted, machine.state);
+        assert_eq!(10, machine.slots[7]);
+        assert_eq!(20, machine.slots[8]);
+        assert_eq!(30, machine.slots[9]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative_mode() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![
+            21201, 3, 2, 5, 99, 0, 0, 0, 0, 0, 0, 0, 0
+        ]);
+
+        let instruction = machine.read_instruction()?;
+        assert_eq!(
+            Instruction::new(Opcode::Add, &[
+                Parameter {
+                    value: 3,
+                    mode: ParameterMode::Relative
+                },
+                Parameter {
+                    value: 2,
+                    mode: ParameterMode::Immediate
+                },
+                Parameter {
+                    value: 5,
+                    mode: ParameterMode::Relative
+                }
+            ]),
+            instruction
+        );
+
+        machine.run()?;
+
+        assert_eq!(7, machine.slots[5]);
+
+        let mut machine = Machine::from_slots(vec![
+            21201, 3, 2, 5, 99, 0, 0, 0, 10, 0, 0, 0, 0
+        ]);
+        machine.relative_base = 5;
+        machine.run()?;
+
+        assert_eq!(12, machine.slots[10]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn self_modifying_code() -> Result<(), OperationalError> {
+        // Runs the instruction at `code` twice, rewriting it from an add into
+        // a multiply in between. A stale decoded instruction would output 7
+        // both times.
+        let mut machine = Machine::from_assembly("
+            code:   ADD  #3, #4, [result]
+                    OUT  [result]
+                    JT   [done], #end
+                    ADD  #1, #0, [done]
+                    ADD  #1102, #0, [code]
+                    JT   #1, #code
+            end:    HLT
+            result: DATA 0
+            done:   DATA 0
+        ").unwrap();
+        machine.run()?;
+
+        assert_eq!(vec![7, 12], machine.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn relative_base_offset() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![109, 4, 99]);
+        assert_eq!(0, machine.relative_base);
+
+        machine.run()?;
+        assert_eq!(4, machine.relative_base);
+
+        Ok(())
+    }
+
+    #[test]
+    fn arithmetic_overflow() -> Result<(), OperationalError> {
+        let add = vec![1101, Value::MAX, 2, 7, 4, 7, 99, 0];
+
+        let error = Machine::from_slots(add.clone()).run().unwrap_err();
+        match error.cause() {
+            OperationalError::ArithmeticOverflow { opcode, left, right, pointer } => {
+                assert_eq!(Opcode::Add, *opcode);
+                assert_eq!(Value::MAX as i128, *left);
+                assert_eq!(2, *right);
+                assert_eq!(0, *pointer);
+            },
+            other => panic!("Expected an overflow, got {:?}", other)
+        }
+
+        let mut wrapping = Machine::from_slots(add.clone());
+        wrapping.set_overflow(Overflow::Wrapping);
+        wrapping.run()?;
+        assert_eq!(vec![Value::MIN + 1], wrapping.read());
+
+        let mut saturating = Machine::from_slots(add);
+        saturating.set_overflow(Overflow::Saturating);
+        saturating.run()?;
+        assert_eq!(vec![Value::MAX], saturating.read());
+
+        let mut multiply = Machine::from_slots(vec![1102, Value::MIN, 2, 7, 99, 0, 0, 0]);
+        assert!(matches!(multiply.run().unwrap_err().cause(),
+                         OperationalError::ArithmeticOverflow { opcode: Opcode::Multiply, .. }));
+
+        let mut offset = Machine::from_slots(vec![109, -1, 109, Value::MIN, 99]);
+        assert!(matches!(offset.run().unwrap_err().cause(),
+                         OperationalError::ArithmeticOverflow { opcode: Opcode::RelativeBaseOffset,
+                                                                pointer: 2, .. }));
+
+        // Reading or writing relative to the base is checked even when the
+        // arithmetic itself wraps.
+        for program in &[vec![109, Value::MAX, 204, 1, 99], vec![109, Value::MAX, 21101, 1, 1, 1, 99]] {
+            let mut relative = Machine::from_slots(program.clone());
+            relative.set_overflow(Overflow::Wrapping);
+            assert!(matches!(relative.run().unwrap_err().cause(),
+                             OperationalError::RelativeAddressOverflow { offset: 1, .. }));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn growable_memory() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![
+            1, 5, 6, 20, 99, 6, 7
+        ]);
+        machine.run()?;
+
+        assert_eq!(&13, machine.get(20)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn day9_examples() -> Result<(), OperationalError> {
+        let mut quine = Machine::from_slots(vec![
+            109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99
+        ]);
+        quine.run()?;
+
+        assert_eq!(
+            vec![109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99],
+            quine.read()
+        );
+
+        let mut large_output_1 = Machine::from_slots(vec![
+            1102,34915192,34915192,7,4,7,99,0
+        ]);
+        large_output_1.run()?;
+
+        assert_eq!(16, format!("{}", large_output_1.read()[0]).len());
+
+        let mut large_output_2 = Machine::from_slots(vec![
+            104,1125899906842624,99
+        ]);
+        large_output_2.run()?;
+
+        assert_eq!(vec![1125899906842624], large_output_2.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn loading() -> Result<(), LoadError> {
+        let path = std::env::temp_dir().join(format!("aoc19-load-{}.txt", std::process::id()));
+
+        fs::write(&path, "OUT #7\nHLT\n")?;
+        assert_eq!(vec![104, 7, 99], load(&path, true)?.memory().to_vec());
+        fs::write(&path, "104,7,99\n")?;
+        assert_eq!(vec![104, 7, 99], load(&path, false)?.memory().to_vec());
+        assert!(matches!(load(&path, true), Err(LoadError::Assembly(_))));
+
+        fs::remove_file(&path)?;
+        assert!(matches!(load(&path, false), Err(LoadError::Io(_))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use thiserror::Error;
+
+use super::{Address, IntoAddress, Machine, MachineState, OperationalError, Value};
+use super::number::Number;
+
+#[derive(Debug, Error)]
+pub enum NetworkError {
+    #[error("Machine {address} failed: {error}")]
+    Machine { address: Address, error: OperationalError },
+    #[error("Packet sent to `{0}`, which isn't a machine or a hook.")]
+    Unroutable(String)
+}
+
+// Machines send packets by outputting the destination, x and y in that
+// order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet<V = Value> {
+    pub destination: Address,
+    pub x: V,
+    pub y: V
+}
+
+// Something listening at an address that isn't a machine, like day 23's NAT
+// at 255.
+pub trait Hook<V = Value>: Send {
+    fn receive(&mut self, packet: Packet<V>);
+
+    // Called at the end of every round where the network was idle. Whatever
+    // this returns is sent out, which will usually wake the network back up.
+    fn idle(&mut self) -> Vec<Packet<V>> {
+        Vec::new()
+    }
+}
+
+// Shared the same way devices are, so the caller can keep a handle on the
+// hook and look at what it's received.
+#[derive(Clone)]
+pub struct SharedHook<V = Value>(pub Arc<Mutex<dyn Hook<V>>>);
+
+impl<V> std::fmt::Debug for SharedHook<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "SharedHook")
+    }
+}
+
+// Day 23's NAT. Remembers every packet sent to it and, whenever the network
+// goes idle, sends the last one on to machine 0.
+#[derive(Debug, Default)]
+pub struct Nat<V = Value> {
+    received: Vec<Packet<V>>,
+    sent: Vec<Packet<V>>
+}
+
+impl<V> Nat<V> {
+    pub fn received(&self) -> &[Packet<V>] {
+        &self.received
+    }
+
+    pub fn sent(&self) -> &[Packet<V>] {
+        &self.sent
+    }
+}
+
+impl<V: Number> Hook<V> for Nat<V> {
+    fn receive(&mut self, packet: Packet<V>) {
+        self.received.push(packet);
+    }
+
+    fn idle(&mut self) -> Vec<Packet<V>> {
+        match self.received.last() {
+            Some(last) => {
+                let packet = Packet { destination: 0, x: last.x.clone(), y: last.y.clone() };
+                self.sent.push(packet.clone());
+                vec![packet]
+            },
+            None => Vec::new()
+        }
+    }
+}
+
+// Why a call to Network::round returned.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoundOutcome {
+    // Something was sent or received.
+    Busy,
+    // Every machine was fed -1 and none of them had anything to say.
+    Idle,
+    // Every machine has halted, so there's nothing left to run.
+    Halted
+}
+
+// A bunch of machines that talk to each other in packets, addressed by their
+// position in the network. Everything runs in rounds on the caller's thread:
+// each round gives each machine, in address order, every packet waiting for
+// it (or -1 if there aren't any) and runs it until it blocks. Packets are
+// delivered as soon as they're sent, so a machine can get a packet from one
+// with a lower address in the same round. That's all fixed, so the same
+// network always does exactly the same thing.
+#[derive(Debug)]
+pub struct Network<V = Value> {
+    machines: Vec<Machine<V>>,
+    queues: Vec<VecDeque<Packet<V>>>,
+    // Output that doesn't make up a whole packet yet.
+    partial: Vec<Vec<V>>,
+    hooks: BTreeMap<Address, SharedHook<V>>,
+    rounds: usize,
+    packets_sent: usize
+}
+
+impl<V: Number> Network<V> {
+    // size copies of program, each told its address as its first input the
+    // way day 23's are.
+    pub fn new(program: &Machine<V>, size: usize) -> Network<V> {
+        Network::from_machines((0..size)
+            .map(|address| {
+                let mut machine = program.clone();
+                machine.write(V::from_value(address as Value));
+                machine
+            })
+            .collect())
+    }
+
+    // Machines that already know their address, if they need to at all.
+    pub fn from_machines(machines: Vec<Machine<V>>) -> Network<V> {
+        let size = machines.len();
+        Network {
+            machines,
+            queues: vec![VecDeque::new(); size],
+            partial: vec![Vec::new(); size],
+            hooks: BTreeMap::new(),
+            rounds: 0,
+            packets_sent: 0
+        }
+    }
+
+    // Packets sent to address go to the hook instead. A hook on a machine's
+    // address takes its packets away from it.
+    pub fn hook<H>(&mut self, address: Address, hook: Arc<Mutex<H>>)
+    where H: Hook<V> + 'static {
+        self.hooks.insert(address, SharedHook(hook));
+    }
+
+    pub fn unhook(&mut self, address: Address) -> Option<SharedHook<V>> {
+        self.hooks.remove(&address)
+    }
+
+    pub fn send(&mut self, packet: Packet<V>) -> Result<(), NetworkError> {
+        if let Some(hook) = self.hooks.get(&packet.destination) {
+            if let Ok(mut hook) = hook.0.lock() {
+                hook.receive(packet);
+            }
+        } else if let Some(queue) = self.queues.get_mut(packet.destination) {
+            queue.push_back(packet);
+        } else {
+            return Err(NetworkError::Unroutable(packet.destination.to_string()));
+        }
+
+        self.packets_sent += 1;
+        Ok(())
+    }
+
+    pub fn round(&mut self) -> Result<RoundOutcome, NetworkError> {
+        self.rounds += 1;
+        let mut idle = true;
+        let mut running = false;
+
+        for address in 0..self.machines.len() {
+            let machine = &mut self.machines[address];
+            if machine.state() == MachineState::Halted {
+                continue;
+            }
+            running = true;
+
+            if !self.queues[address].is_empty() {
+                idle = false;
+                for packet in self.queues[address].drain(..) {
+                    machine.write(packet.x);
+                    machine.write(packet.y);
+                }
+            } else if machine.pending_input().is_empty() {
+                machine.write(V::from_value(-1));
+            } else {
+                // It hasn't even used up what it was given last time.
+                idle = false;
+            }
+
+            machine.run().map_err(|error| NetworkError::Machine { address, error })?;
+
+            let output = machine.read();
+            if !output.is_empty() {
+                idle = false;
+            }
+            self.partial[address].extend(output);
+
+            while self.partial[address].len() >= 3 {
+                let mut values = self.partial[address].drain(..3);
+                let (destination, x, y) = (values.next().unwrap(), values.next().unwrap(),
+                                           values.next().unwrap());
+                drop(values);
+
+                let destination = destination.clone().into_addr()
+                    .map_err(|_| NetworkError::Unroutable(destination.to_string()))?;
+                self.send(Packet { destination, x, y })?;
+            }
+        }
+
+        if !running {
+            return Ok(RoundOutcome::Halted);
+        }
+        if !idle {
+            return Ok(RoundOutcome::Busy);
+        }
+
+        let mut wake_up = Vec::new();
+        for hook in self.hooks.values() {
+            if let Ok(mut hook) = hook.0.lock() {
+                wake_up.extend(hook.idle());
+            }
+        }
+        for packet in wake_up {
+            self.send(packet)?;
+        }
+        Ok(RoundOutcome::Idle)
+    }
+
+    // Runs rounds until the network is idle or every machine has halted.
+    // Like Machine::run, this never returns for a network that stays busy.
+    pub fn run_until_idle(&mut self) -> Result<RoundOutcome, NetworkError> {
+        loop {
+            match self.round()? {
+                RoundOutcome::Busy => {},
+                outcome => return Ok(outcome)
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.machines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.machines.is_empty()
+    }
+
+    pub fn machine(&self, address: Address) -> Option<&Machine<V>> {
+        self.machines.get(address)
+    }
+
+    // Packets sent to address that it hasn't been given yet.
+    pub fn queued(&self, address: Address) -> Option<&VecDeque<Packet<V>>> {
+        self.queues.get(address)
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn packets_sent(&self) -> usize {
+        self.packets_sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Passes every packet it gets on to the next address up, with y one
+    // bigger.
+    fn relay() -> Machine {
+        Machine::from_assembly("
+                    IN   [address]
+                    ADD  [address], #1, [next]
+            start:  IN   [x]
+                    EQ   [x], #-1, [empty]
+                    JT   [empty], #start
+                    IN   [y]
+                    ADD  [y], #1, [y]
+                    OUT  [next]
+                    OUT  [x]
+                    OUT  [y]
+                    JT   #1, #start
+            address: DATA 0
+            next:   DATA 0
+            x:      DATA 0
+            y:      DATA 0
+            empty:  DATA 0
+        ").unwrap()
+    }
+
+    #[derive(Default)]
+    struct Recorder(Vec<Packet>);
+
+    impl Hook for Recorder {
+        fn receive(&mut self, packet: Packet) {
+            self.0.push(packet);
+        }
+    }
+
+    #[test]
+    fn routes_packets_until_idle() -> Result<(), NetworkError> {
+        let mut network = Network::new(&relay(), 3);
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
+        network.hook(3, recorder.clone());
+
+        network.send(Packet { destination: 0, x: 5, y: 0 })?;
+        assert_eq!(RoundOutcome::Idle, network.run_until_idle()?);
+
+        // The packet makes it all the way along in the first round, and the
+        // second is all -1s.
+        assert_eq!(2, network.rounds());
+        assert_eq!(4, network.packets_sent());
+        assert_eq!(vec![Packet { destination: 3, x: 5, y: 3 }], recorder.lock().unwrap().0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn nat_wakes_the_network_up() -> Result<(), NetworkError> {
+        let mut network = Network::new(&relay(), 3);
+        let nat = Arc::new(Mutex::new(Nat::default()));
+        network.hook(3, nat.clone());
+
+        network.send(Packet { destination: 0, x: 5, y: 0 })?;
+        network.run_until_idle()?;
+        assert_eq!(1, network.queued(0).unwrap().len());
+        assert_eq!(RoundOutcome::Busy, network.round()?);
+        assert_eq!(RoundOutcome::Idle, network.round()?);
+
+        let ys: Vec<Value> = nat.lock().unwrap().sent().iter().map(|packet| packet.y).collect();
+        assert_eq!(vec![3, 6], ys);
+
+        Ok(())
+    }
+
+    #[test]
+    fn unroutable_packets() {
+        let mut network = Network::new(&relay(), 3);
+        assert!(matches!(network.send(Packet { destination: 7, x: 1, y: 1 }),
+                         Err(NetworkError::Unroutable(_))));
+
+        // Nothing's listening at 3.
+        network.send(Packet { destination: 0, x: 1, y: 1 }).unwrap();
+        assert!(matches!(network.round(), Err(NetworkError::Unroutable(destination)) if destination == "3"));
+    }
+
+    #[test]
+    fn halted_networks() -> Result<(), NetworkError> {
+        // They halt before sending anything, so the first round looks idle.
+        let mut network = Network::from_machines(vec![Machine::from_slots(vec![99]); 4]);
+        assert_eq!(RoundOutcome::Idle, network.round()?);
+        assert_eq!(RoundOutcome::Halted, network.round()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::ops::{Add, Mul};
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use super::{Address, IntoAddress, OperationalError, Value};
+
+// What Add, Multiply and RelativeBaseOffset do when the result doesn't fit.
+// Checked is the default since a wrapped or saturated number is almost never
+// the right answer to a puzzle, it just looks like one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Overflow {
+    // Stop with an ArithmeticOverflow error.
+    Checked,
+    // Wrap around in two's complement, like C would.
+    Wrapping,
+    // Clamp to the biggest or smallest number of the type.
+    Saturating
+}
+
+// Anything a Machine can keep in memory. Every puzzle is fine with Value, but
+// Add and Multiply overflow it on programs that work with really big numbers,
+// so machines can be made over i64, i128 or BigInt instead.
+//
+// Instructions and addresses always have to fit in a Value. Only the values
+// programs compute with can be bigger.
+pub trait Number: Clone + Default + Eq + Ord + Hash + Debug + Display + FromStr + IntoAddress
+    + ToPrimitive + Add<Output=Self> + Mul<Output=Self> + Send + Sync + 'static {
+    fn from_value(value: Value) -> Self;
+
+    // None if this doesn't fit in a Value.
+    fn to_value(&self) -> Option<Value>;
+
+    // These are None only for a Checked overflow.
+    fn add_with(&self, other: &Self, overflow: Overflow) -> Option<Self>;
+    fn mul_with(&self, other: &Self, overflow: Overflow) -> Option<Self>;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::from_value(0)
+    }
+
+    fn is_negative(&self) -> bool {
+        *self < Self::from_value(0)
+    }
+
+    // For places that want a Value no matter what, like error messages. Too
+    // big numbers become the biggest Value with the same sign.
+    fn saturating_value(&self) -> Value {
+        match self.to_value() {
+            Some(value) => value,
+            None if self.is_negative() => Value::MIN,
+            None => Value::MAX
+        }
+    }
+}
+
+// Every primitive has all three kinds of arithmetic built in already.
+macro_rules! primitive_arithmetic {
+    () => {
+        fn add_with(&self, other: &Self, overflow: Overflow) -> Option<Self> {
+            match overflow {
+                Overflow::Checked => self.checked_add(*other),
+                Overflow::Wrapping => Some(self.wrapping_add(*other)),
+                Overflow::Saturating => Some(self.saturating_add(*other))
+            }
+        }
+
+        fn mul_with(&self, other: &Self, overflow: Overflow) -> Option<Self> {
+            match overflow {
+                Overflow::Checked => self.checked_mul(*other),
+                Overflow::Wrapping => Some(self.wrapping_mul(*other)),
+                Overflow::Saturating => Some(self.saturating_mul(*other))
+            }
+        }
+    }
+}
+
+fn number_into_addr<N: Number>(number: N) -> Result<Address, OperationalError> {
+    match number.to_value() {
+        Some(value) => value.into_addr(),
+        None => Err(OperationalError::Unrepresentable(number.to_string()))
+    }
+}
+
+impl Number for Value {
+    fn from_value(value: Value) -> Self {
+        value
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        Some(*self)
+    }
+
+    primitive_arithmetic!();
+}
+
+impl Number for i64 {
+    fn from_value(value: Value) -> Self {
+        value as i64
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        self.to_isize()
+    }
+
+    primitive_arithmetic!();
+}
+
+impl IntoAddress for i64 {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        number_into_addr(self)
+    }
+}
+
+impl Number for i128 {
+    fn from_value(value: Value) -> Self {
+        value as i128
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        self.to_isize()
+    }
+
+    primitive_arithmetic!();
+}
+
+impl IntoAddress for i128 {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        number_into_addr(self)
+    }
+}
+
+impl Number for BigInt {
+    fn from_value(value: Value) -> Self {
+        BigInt::from(value)
+    }
+
+    fn to_value(&self) -> Option<Value> {
+        self.to_isize()
+    }
+
+    // There's nothing to overflow, so all three modes are the same.
+    fn add_with(&self, other: &Self, _overflow: Overflow) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn mul_with(&self, other: &Self, _overflow: Overflow) -> Option<Self> {
+        Some(self * other)
+    }
+}
+
+impl IntoAddress for BigInt {
+    fn into_addr(self) -> Result<Address, OperationalError> {
+        number_into_addr(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Machine;
+
+    // Squares its input and outputs the result.
+    const SQUARE: &str = "3,9,2,9,9,9,4,9,99";
+
+    #[test]
+    fn wide_values() -> Result<(), OperationalError> {
+        let mut machine: Machine<i128> = SQUARE.parse().unwrap();
+        machine.write(1 << 40);
+        machine.run()?;
+        assert_eq!(vec![1 << 80], machine.read());
+
+        let mut machine: Machine<BigInt> = SQUARE.parse().unwrap();
+        let big: BigInt = "123456789012345678901234567890".parse().unwrap();
+        machine.write(big.clone());
+        machine.run()?;
+        assert_eq!(vec![&big * &big], machine.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parses_values_wider_than_isize() {
+        let machine: Machine<BigInt> = "104,99999999999999999999999999,99".parse().unwrap();
+        assert_eq!("99999999999999999999999999", machine.memory()[1].to_string());
+
+        assert!("104,99999999999999999999999999,99".parse::<Machine<i64>>().is_err());
+    }
+
+    #[test]
+    fn addresses() {
+        assert_eq!(12, 12i64.into_addr().unwrap());
+        assert_eq!(12, 12i128.into_addr().unwrap());
+        assert_eq!(12, BigInt::from(12).into_addr().unwrap());
+        assert!(matches!((-3i128).into_addr(), Err(OperationalError::NegativeAddress(-3))));
+        assert!(matches!((1i128 << 100).into_addr(),
+                         Err(OperationalError::Unrepresentable(_))));
+
+        let mut machine: Machine<i128> = "1,0,0,0,99".parse().unwrap();
+        assert_eq!(&1, machine.get(0i128).unwrap());
+        assert!(machine.set(1i128 << 100, 5).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Address, Machine, Opcode};
+use super::disassembler::Line;
+use super::number::Number;
+
+// How many entries each of the report's top lists gets.
+const REPORT_LENGTH: usize = 10;
+
+// Counts of everything a machine did while it was being profiled. Reads and
+// writes are of memory by instructions' parameters. Fetching the instructions
+// themselves doesn't count as a read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    instructions: usize,
+    executions: HashMap<Address, usize>,
+    opcodes: HashMap<Opcode, usize>,
+    reads: HashMap<Address, usize>,
+    writes: HashMap<Address, usize>
+}
+
+fn count<K: std::hash::Hash + Eq>(counts: &HashMap<K, usize>, key: &K) -> usize {
+    counts.get(key).copied().unwrap_or(0)
+}
+
+// Biggest count first, and lowest address first between equal counts so
+// reports come out the same every time.
+fn top(counts: &HashMap<Address, usize>) -> Vec<(Address, usize)> {
+    let mut counts: Vec<(Address, usize)> = counts.iter().map(|(&a, &c)| (a, c)).collect();
+    counts.sort_by_key(|&(address, count)| (std::cmp::Reverse(count), address));
+    counts.truncate(REPORT_LENGTH);
+    counts
+}
+
+impl Profile {
+    pub(super) fn executed(&mut self, address: Address, opcode: Opcode) {
+        self.instructions += 1;
+        *self.executions.entry(address).or_insert(0) += 1;
+        *self.opcodes.entry(opcode).or_insert(0) += 1;
+    }
+
+    pub(super) fn read(&mut self, address: Address) {
+        *self.reads.entry(address).or_insert(0) += 1;
+    }
+
+    pub(super) fn wrote(&mut self, address: Address) {
+        *self.writes.entry(address).or_insert(0) += 1;
+    }
+
+    pub fn instructions(&self) -> usize {
+        self.instructions
+    }
+
+    pub fn executions(&self, address: Address) -> usize {
+        count(&self.executions, &address)
+    }
+
+    pub fn opcode_count(&self, opcode: Opcode) -> usize {
+        count(&self.opcodes, &opcode)
+    }
+
+    pub fn reads(&self, address: Address) -> usize {
+        count(&self.reads, &address)
+    }
+
+    pub fn writes(&self, address: Address) -> usize {
+        count(&self.writes, &address)
+    }
+
+    // How many of the instructions in lines were executed at least once,
+    // and how many instructions there are. Instructions executed somewhere
+    // lines doesn't start one count on both sides.
+    pub fn coverage<V>(&self, lines: &[Line<V>]) -> (usize, usize) {
+        let instructions = lines.iter().filter(|line| matches!(line, Line::Instruction { .. }));
+        let executed = instructions.clone().filter(|line| self.executions(line.address()) > 0);
+        let unattributed = self.unattributed(lines).len();
+        (executed.count() + unattributed, instructions.count() + unattributed)
+    }
+
+    // Addresses executed that lines doesn't start an instruction at, and how
+    // many times, lowest address first. A linear sweep can land out of step
+    // with the code that actually runs, where something jumps into what it
+    // took for the middle of an instruction or code was written after it
+    // was disassembled.
+    pub fn unattributed<V>(&self, lines: &[Line<V>]) -> Vec<(Address, usize)> {
+        let starts: HashSet<Address> = lines.iter()
+            .filter(|line| matches!(line, Line::Instruction { .. }))
+            .map(|line| line.address())
+            .collect();
+        let mut unattributed: Vec<(Address, usize)> = self.executions.iter()
+            .filter(|(address, _)| !starts.contains(address))
+            .map(|(&address, &count)| (address, count))
+            .collect();
+        unattributed.sort();
+        unattributed
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = vec![format!("{} instructions executed.", self.instructions)];
+
+        report.push("\nBy opcode:".to_string());
+        let mut opcodes: Vec<(Opcode, usize)> = self.opcodes.iter().map(|(&o, &c)| (o, c)).collect();
+        opcodes.sort_by_key(|&(opcode, count)| (std::cmp::Reverse(count), opcode.as_int()));
+        for (opcode, count) in opcodes {
+            let percent = 100.0 * count as f64 / self.instructions as f64;
+            report.push(format!("  {:<4} {:>10} {:>6.1}%", opcode.mnemonic(), count, percent));
+        }
+
+        let sections = [
+            ("Hottest addresses", &self.executions),
+            ("Most read", &self.reads),
+            ("Most written", &self.writes)
+        ];
+        for (title, counts) in sections.iter() {
+            report.push(format!("\n{}:", title));
+            for (address, count) in top(counts) {
+                report.push(format!("  {:>5} {:>10}", address, count));
+            }
+        }
+
+        report.join("\n")
+    }
+
+    // The lines with how many times each instruction was executed next to
+    // them, or a dash if it never was, and how often the words in each line
+    // were read and written as a comment on the end.
+    pub fn listing<V: Number>(&self, lines: &[Line<V>]) -> String {
+        let mut listing: Vec<String> = lines.iter()
+            .map(|line| {
+                let (executions, width) = match line {
+                    Line::Instruction { address, instruction } => {
+                        let executions = match self.executions(*address) {
+                            0 => "-".to_string(),
+                            count => count.to_string()
+                        };
+                        (executions, instruction.opcode.parameter_count() + 1)
+                    },
+                    Line::Data { .. } => (String::new(), 1)
+                };
+
+                let words = line.address()..line.address() + width;
+                let reads: usize = words.clone().map(|address| self.reads(address)).sum();
+                let writes: usize = words.map(|address| self.writes(address)).sum();
+                let mut annotated = format!("{:>10}  {}", executions, line);
+                if reads > 0 || writes > 0 {
+                    annotated.push_str(&format!(" ; read {}, written {}", reads, writes));
+                }
+                annotated
+            })
+            .collect();
+
+        let unattributed = self.unattributed(lines);
+        if !unattributed.is_empty() {
+            listing.push("\nAlso executed, where no line above starts an instruction:".to_string());
+            for (address, count) in unattributed {
+                listing.push(format!("{:>10}  {:>5}", count, address));
+            }
+        }
+
+        let (executed, total) = self.coverage(lines);
+        listing.push(format!("\n{} of {} instructions executed.", executed, total));
+        listing.join("\n")
+    }
+}
+
+impl<V: Number> Machine<V> {
+    // Starts counting from zero, throwing away any profile already going.
+    pub fn start_profiling(&mut self) {
+        self.profile = Some(Profile::default());
+    }
+
+    pub fn stop_profiling(&mut self) -> Option<Profile> {
+        self.profile.take()
+    }
+
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::OperationalError;
+
+    // Counts [12] up to 3 and halts.
+    fn counter() -> Machine {
+        Machine::from_slots(vec![1001, 12, 1, 12, 1007, 12, 3, 13, 1005, 13, 0, 99, 0, 0])
+    }
+
+    #[test]
+    fn counts() -> Result<(), OperationalError> {
+        let mut machine = counter();
+        machine.start_profiling();
+        machine.run()?;
+        let profile = machine.stop_profiling().unwrap();
+
+        assert_eq!(10, profile.instructions());
+        assert_eq!(vec![3, 3, 3, 1], vec![0, 4, 8, 11].into_iter()
+            .map(|address| profile.executions(address))
+            .collect::<Vec<usize>>());
+        assert_eq!(3, profile.opcode_count(Opcode::Add));
+        assert_eq!(1, profile.opcode_count(Opcode::Halt));
+        assert_eq!(0, profile.opcode_count(Opcode::Output));
+        assert_eq!((6, 3), (profile.reads(12), profile.writes(12)));
+        assert_eq!((3, 3), (profile.reads(13), profile.writes(13)));
+        assert!(machine.profile().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn report() -> Result<(), OperationalError> {
+        let mut machine = counter();
+        machine.start_profiling();
+        machine.run()?;
+
+        let report = machine.profile().unwrap().report();
+        assert!(report.starts_with("10 instructions executed.\n\nBy opcode:\n  ADD           3   30.0%\n"));
+        assert!(report.contains("  HLT           1   10.0%"));
+        assert!(report.contains("Most read:\n     12          6\n     13          3\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn listing() -> Result<(), OperationalError> {
+        let mut machine = Machine::from_slots(vec![1008, 8, 1, 8, 1005, 8, 10, 104, 1, 99, 99]);
+        machine.start_profiling();
+        machine.run()?;
+
+        assert_eq!(
+            "         1      0: EQ   [8], #1, [8]\n\
+             \x20        1      4: JT   [8], #10\n\
+             \x20        -      7: OUT  #1 ; read 2, written 1\n\
+             \x20        -      9: HLT\n\
+             \x20        1     10: HLT\n\
+             \n\
+             3 of 5 instructions executed.",
+            machine.profile().unwrap().listing(&machine.disassemble())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn misaligned_code() -> Result<(), OperationalError> {
+        // Jumps to 5, which the sweep takes for the middle of an ADD.
+        let mut machine = Machine::from_slots(vec![1105, 1, 5, 1101, 0, 104, 7, 99]);
+        machine.start_profiling();
+        machine.run()?;
+        let profile = machine.profile().unwrap();
+
+        assert_eq!(vec![(5, 1)], profile.unattributed(&machine.disassemble()));
+        assert_eq!((3, 4), profile.coverage(&machine.disassemble()));
+        assert_eq!(
+            "         1      0: JT   #1, #5\n\
+             \x20        -      3: ADD  #0, #104, [7]\n\
+             \x20        1      7: HLT\n\
+             \n\
+             Also executed, where no line above starts an instruction:\n\
+             \x20        1      5\n\
+             \n\
+             3 of 4 instructions executed.",
+            profile.listing(&machine.disassemble())
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,395 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use thiserror::Error;
+
+use super::{Address, Value, Machine, MachineState, OperationalError};
+use super::memory::{Backend, Memory};
+use super::number::{Number, Overflow};
+
+// Snapshots are plain text, one field per line, so they're easy to poke at
+// by hand:
+//
+//     intcode-snapshot 2
+//     pointer 12
+//     relative_base 0
+//     state Blocked
+//     instruction_counter 40
+//     input_pointer 2
+//     input 5,6
+//     output_pointer 0
+//     output 99
+//     overflow Checked
+//     memory_limit 16777216
+//     loop_detection false
+//     backend Dense
+//     memory_len 120
+//     memory 0 3,12,...
+//
+// Lists are comma separated and may be empty. memory_limit is `none` if
+// there isn't one. Each memory line is an address and the values starting
+// there, and anything they don't cover is zero. Dense memory is one line,
+// paged memory a line per allocated page, so a machine that's written to
+// some enormous address doesn't make an enormous snapshot.
+//
+// Breakpoints, tracers, devices, timeouts, profiles and history aren't saved
+// since they belong to whoever is driving the machine, not to the program.
+// Bump VERSION on any change to this layout.
+
+pub const HEADER: &str = "intcode-snapshot";
+pub const VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Not an Intcode snapshot.")]
+    MissingHeader,
+    #[error("Snapshot version `{0}` is not supported, expected {}.", VERSION)]
+    UnsupportedVersion(String),
+    #[error("Snapshot is missing the `{0}` field.")]
+    MissingField(&'static str),
+    #[error("Snapshot field `{0}` has invalid value `{1}`.")]
+    InvalidField(&'static str, String),
+    #[error("Error reading or writing snapshot: {0}")]
+    IoError(#[from] io::Error)
+}
+
+fn join<V: Number>(values: &[V]) -> String {
+    values.iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn parse_overflow(overflow: &str) -> Option<Overflow> {
+    match overflow {
+        "Checked" => Some(Overflow::Checked),
+        "Wrapping" => Some(Overflow::Wrapping),
+        "Saturating" => Some(Overflow::Saturating),
+        _ => None
+    }
+}
+
+fn parse_backend(backend: &str) -> Option<Backend> {
+    match backend {
+        "Dense" => Some(Backend::Dense),
+        "Paged" => Some(Backend::Paged),
+        _ => None
+    }
+}
+
+fn parse_state(state: &str) -> Option<MachineState> {
+    match state {
+        "Running" => Some(MachineState::Running),
+        "Halted" => Some(MachineState::Halted),
+        "Blocked" => Some(MachineState::Blocked),
+        _ => None
+    }
+}
+
+struct Fields<'a> {
+    lines: Vec<(&'a str, &'a str)>
+}
+
+impl<'a> Fields<'a> {
+    fn get(&self, name: &'static str) -> Result<&'a str, SnapshotError> {
+        self.all(name).next().ok_or(SnapshotError::MissingField(name))
+    }
+
+    // For fields that can be there more than once.
+    fn all<'b>(&'b self, name: &'static str) -> impl Iterator<Item = &'a str> + 'b {
+        self.lines.iter()
+            .filter(move |(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn parsed<T>(&self, name: &'static str, parse: fn(&str) -> Option<T>) -> Result<T, SnapshotError> {
+        let value = self.get(name)?;
+        parse(value).ok_or_else(|| SnapshotError::InvalidField(name, value.to_string()))
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &'static str) -> Result<T, SnapshotError> {
+        let value = self.get(name)?;
+        value.parse().map_err(|_| SnapshotError::InvalidField(name, value.to_string()))
+    }
+
+    fn list<V: Number>(&self, name: &'static str) -> Result<Vec<V>, SnapshotError> {
+        parse_list(name, self.get(name)?)
+    }
+
+    fn memory<V: Number>(&self) -> Result<Vec<(Address, Vec<V>)>, SnapshotError> {
+        self.all("memory")
+            .map(|line| {
+                let (start, values) = line.split_at(line.find(' ').unwrap_or(line.len()));
+                let start = start.parse()
+                    .map_err(|_| SnapshotError::InvalidField("memory", line.to_string()))?;
+                Ok((start, parse_list("memory", values.trim())?))
+            })
+            .collect()
+    }
+}
+
+fn parse_list<V: Number>(name: &'static str, value: &str) -> Result<Vec<V>, SnapshotError> {
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    value.split(',')
+        .map(|token| token.trim().parse::<V>()
+             .map_err(|_| SnapshotError::InvalidField(name, token.to_string())))
+        .collect()
+}
+
+pub fn is_snapshot(text: &str) -> bool {
+    text.trim_start().starts_with(HEADER)
+}
+
+impl<V: Number> Machine<V> {
+    pub fn to_snapshot(&self) -> String {
+        format!("{} {}\n\
+                 pointer {}\n\
+                 relative_base {}\n\
+                 state {}\n\
+                 instruction_counter {}\n\
+                 input_pointer {}\n\
+                 input {}\n\
+                 output_pointer {}\n\
+                 output {}\n\
+                 overflow {:?}\n\
+                 memory_limit {}\n\
+                 loop_detection {}\n\
+                 backend {:?}\n\
+                 memory_len {}\n\
+                 {}",
+                HEADER, VERSION,
+                self.pointer,
+                self.relative_base,
+                self.state,
+                self.instruction_counter,
+                self.input_pointer,
+                join(&self.input),
+                self.output_pointer,
+                join(&self.output),
+                self.overflow,
+                self.memory_limit.map_or("none".to_string(), |limit| limit.to_string()),
+                self.loop_detection(),
+                self.slots.backend(),
+                self.slots.len(),
+                self.slots.chunks().into_iter()
+                    .map(|(start, values)| format!("memory {} {}\n", start, join(values)))
+                    .collect::<String>())
+    }
+
+    pub fn from_snapshot(snapshot: &str) -> Result<Machine<V>, SnapshotError> {
+        let mut lines = snapshot.trim().lines();
+
+        let header = lines.next().unwrap_or("");
+        match header.strip_prefix(HEADER) {
+            Some(version) if version.trim() == VERSION.to_string() => {},
+            Some(version) => return Err(SnapshotError::UnsupportedVersion(version.trim().to_string())),
+            None => return Err(SnapshotError::MissingHeader)
+        }
+
+        let fields = Fields {
+            lines: lines
+                .map(|line| {
+                    let line = line.trim();
+                    match line.find(' ') {
+                        Some(split) => (&line[..split], line[split + 1..].trim()),
+                        None => (line, "")
+                    }
+                })
+                .collect()
+        };
+
+        let state = fields.get("state")?;
+        let memory_limit = match fields.get("memory_limit")? {
+            "none" => None,
+            _ => Some(fields.number::<usize>("memory_limit")?)
+        };
+        let backend = fields.parsed("backend", parse_backend)?;
+        let len = fields.number::<usize>("memory_len")?;
+        // Don't even try to allocate dense memory that's over the limit.
+        if let Some(limit) = memory_limit.filter(|limit| backend == Backend::Dense && len > *limit) {
+            return Err(SnapshotError::InvalidField("memory_len", len.to_string()));
+        }
+
+        let mut machine = Machine::with_slots(Vec::new());
+        machine.slots = Memory::from_chunks(backend, len, memory_limit, fields.memory()?)
+            .map_err(|error| match error {
+                OperationalError::OutOfRange(start) => SnapshotError::InvalidField("memory", start.to_string()),
+                _ => SnapshotError::InvalidField("memory_limit", fields.get("memory_limit").unwrap_or("").to_string())
+            })?;
+        machine.memory_limit = memory_limit;
+        machine.overflow = fields.parsed("overflow", parse_overflow)?;
+        machine.set_loop_detection(fields.parsed("loop_detection", |value| value.parse().ok())?);
+        machine.pointer = fields.number::<Address>("pointer")?;
+        machine.relative_base = fields.number::<V>("relative_base")?;
+        machine.state = parse_state(state)
+            .ok_or_else(|| SnapshotError::InvalidField("state", state.to_string()))?;
+        machine.instruction_counter = fields.number("instruction_counter")?;
+        machine.input_pointer = fields.number("input_pointer")?;
+        machine.input = fields.list("input")?;
+        machine.output_pointer = fields.number("output_pointer")?;
+        machine.output = fields.list("output")?;
+
+        Ok(machine)
+    }
+
+    pub fn save_snapshot<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        fs::write(path, self.to_snapshot())?;
+        Ok(())
+    }
+
+    pub fn load_snapshot<P: AsRef<Path>>(path: P) -> Result<Machine<V>, SnapshotError> {
+        Machine::from_snapshot(&fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::OperationalError;
+    use super::super::memory::PAGE_SIZE;
+
+    // Reads two numbers and outputs their sum, twice.
+    fn adder() -> Machine {
+        Machine::from_slots(vec![
+            3, 13, 3, 14, 1, 13, 14, 15, 4, 15, 1105, 1, 0, 0, 0, 0
+        ])
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), OperationalError> {
+        let mut machine = adder();
+        machine.write(2);
+        machine.write(3);
+        machine.write(10);
+        machine.run()?;
+
+        let snapshot = machine.to_snapshot();
+        assert!(snapshot.starts_with("intcode-snapshot 2\npointer 2\n"));
+        assert!(snapshot.contains("\nstate Blocked\n"));
+        assert!(snapshot.contains("\noutput 5\n"));
+
+        let mut restored: Machine = Machine::from_snapshot(&snapshot).unwrap();
+        assert_eq!(snapshot, restored.to_snapshot());
+
+        machine.write(20);
+        restored.write(20);
+        machine.run()?;
+        restored.run()?;
+        assert_eq!(machine.to_snapshot(), restored.to_snapshot());
+        assert_eq!(vec![5, 30], restored.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn machine_settings() -> Result<(), OperationalError> {
+        // Writes 7 to address 10^12, then adds one to its input and outputs
+        // it.
+        let mut machine = Machine::from_slots(vec![
+            1101, 3, 4, 1_000_000_000_000, 3, 13, 1001, 13, 1, 13, 4, 13, 99, 0
+        ]);
+        machine.set_memory_backend(Backend::Paged)?;
+        machine.set_memory_limit(Some(4 * PAGE_SIZE));
+        machine.set_overflow(Overflow::Wrapping);
+        machine.set_loop_detection(true);
+        machine.run()?;
+
+        let snapshot = machine.to_snapshot();
+        assert!(snapshot.contains("\nbackend Paged\nmemory_len 1000000000001\n"));
+        assert!(snapshot.ends_with("\nmemory 1000000000000 7\n"));
+
+        let mut restored: Machine = Machine::from_snapshot(&snapshot).unwrap();
+        assert_eq!(snapshot, restored.to_snapshot());
+        assert_eq!(Backend::Paged, restored.memory().backend());
+        assert_eq!(Some(4 * PAGE_SIZE), restored.memory_limit());
+        assert_eq!(Overflow::Wrapping, restored.overflow());
+        assert!(restored.loop_detection());
+
+        restored.write(Value::MAX);
+        restored.run()?;
+        assert_eq!(vec![Value::MIN], restored.read());
+
+        // Dense memory bigger than its limit isn't even allocated.
+        let dense = adder().to_snapshot().replace("memory_len 16", "memory_len 1000000000000");
+        assert!(matches!(
+            Machine::<Value>::from_snapshot(&dense),
+            Err(SnapshotError::InvalidField("memory_len", _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn from_str_accepts_snapshots() {
+        let snapshot = adder().to_snapshot();
+        let machine = Machine::from_str(&snapshot).unwrap();
+        assert_eq!(adder().to_snapshot(), machine.to_snapshot());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("aoc19-snapshot-{}.txt", std::process::id()));
+        let mut machine = adder();
+        machine.write(7);
+
+        machine.save_snapshot(&path).unwrap();
+        let loaded: Machine = Machine::load_snapshot(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(machine.to_snapshot(), loaded.to_snapshot());
+    }
+
+    #[test]
+    fn invalid_snapshots() {
+        let snapshot = adder().to_snapshot();
+
+        assert!(matches!(Machine::<Value>::from_snapshot("1,2,3"), Err(SnapshotError::MissingHeader)));
+        assert!(matches!(
+            Machine::<Value>::from_snapshot(&snapshot.replace("snapshot 2", "snapshot 1")),
+            Err(SnapshotError::UnsupportedVersion(ref v)) if v == "1"
+        ));
+        assert!(matches!(
+            Machine::<Value>::from_snapshot(&snapshot.replacen("\npointer 0\n", "\n", 1)),
+            Err(SnapshotError::MissingField("pointer"))
+        ));
+        assert!(matches!(
+            Machine::<Value>::from_snapshot(&snapshot.replace("state Running", "state Sleeping")),
+            Err(SnapshotError::InvalidField("state", _))
+        ));
+    }
+
+    #[test]
+    fn invalid_memory() {
+        let dense = adder().to_snapshot();
+        let mut paged = adder();
+        paged.set_memory_backend(Backend::Paged).unwrap();
+        paged.set_memory_limit(Some(2 * PAGE_SIZE));
+        let paged = paged.to_snapshot().replace("memory_len 16", "memory_len 1000000000000");
+
+        for (snapshot, last) in &[(dense, 15usize), (paged.clone(), 999_999_999_999)] {
+            // Past the end of memory.
+            assert!(matches!(
+                Machine::<Value>::from_snapshot(&format!("{}memory {} 1,2\n", snapshot, last)),
+                Err(SnapshotError::InvalidField("memory", ref start)) if *start == last.to_string()
+            ));
+            // So far past it that the end doesn't fit in an address.
+            assert!(matches!(
+                Machine::<Value>::from_snapshot(&format!("{}memory {} 1,2\n", snapshot, usize::MAX)),
+                Err(SnapshotError::InvalidField("memory", _))
+            ));
+        }
+
+        // Fits in memory_len, but needs more pages than the limit allows.
+        let spread = (1..4)
+            .map(|page| format!("memory {} 1\n", page * 1_000_000 * PAGE_SIZE))
+            .collect::<String>();
+        assert!(matches!(
+            Machine::<Value>::from_snapshot(&format!("{}{}", paged, spread)),
+            Err(SnapshotError::InvalidField("memory_limit", _))
+        ));
+    }
+}
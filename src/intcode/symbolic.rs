@@ -0,0 +1,558 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+use thiserror::Error;
+
+use super::{Address, Instruction, IntoAddress, Machine, MachineState, OperationalError, Opcode,
+            ParameterMode, RunOutcome, Value};
+use super::memory::DEFAULT_MEMORY_LIMIT;
+
+#[derive(Debug, Error)]
+pub enum SymbolicError {
+    #[error("Can't run the instruction at {address} without knowing what `{value}` is.")]
+    Undecidable { address: Address, value: String },
+    #[error("`{0}` isn't linear in its variables.")]
+    NotLinear(String),
+    #[error("No range was given for variable `{0}`.")]
+    Unbound(String),
+    #[error("The program stopped without halting: {0:?}.")]
+    NotHalted(RunOutcome),
+    #[error("{0}")]
+    Machine(#[from] OperationalError)
+}
+
+// c + a*x + b*y + ..., which covers plain numbers and variables too.
+// Variables with a zero coefficient are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Linear {
+    constant: Value,
+    terms: BTreeMap<String, Value>
+}
+
+impl Linear {
+    pub fn constant(&self) -> Value {
+        self.constant
+    }
+
+    pub fn coefficient(&self, variable: &str) -> Value {
+        self.terms.get(variable).copied().unwrap_or(0)
+    }
+
+    // None if any of it overflows.
+    fn add(&self, other: &Linear) -> Option<Linear> {
+        let mut sum = self.clone();
+        sum.constant = sum.constant.checked_add(other.constant)?;
+        for (variable, coefficient) in &other.terms {
+            let total = sum.coefficient(variable).checked_add(*coefficient)?;
+            if total == 0 {
+                sum.terms.remove(variable);
+            } else {
+                sum.terms.insert(variable.clone(), total);
+            }
+        }
+        Some(sum)
+    }
+
+    fn scale(&self, factor: Value) -> Option<Linear> {
+        if factor == 0 {
+            return Some(Linear::default());
+        }
+        let mut terms = BTreeMap::new();
+        for (variable, coefficient) in &self.terms {
+            terms.insert(variable.clone(), coefficient.checked_mul(factor)?);
+        }
+        Some(Linear { constant: self.constant.checked_mul(factor)?, terms })
+    }
+}
+
+impl std::fmt::Display for Linear {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts: Vec<(bool, String)> = self.terms.iter()
+            .map(|(variable, &coefficient)| match coefficient {
+                1 => (false, variable.clone()),
+                -1 => (true, variable.clone()),
+                c if c < 0 => (true, format!("{}*{}", c.unsigned_abs(), variable)),
+                c => (false, format!("{}*{}", c, variable))
+            })
+            .collect();
+        if self.constant != 0 || parts.is_empty() {
+            parts.push((self.constant < 0, self.constant.unsigned_abs().to_string()));
+        }
+
+        for (i, (negative, part)) in parts.iter().enumerate() {
+            match (i, negative) {
+                (0, true) => write!(f, "-{}", part)?,
+                (0, false) => write!(f, "{}", part)?,
+                (_, true) => write!(f, " - {}", part)?,
+                (_, false) => write!(f, " + {}", part)?
+            }
+        }
+        Ok(())
+    }
+}
+
+// What a symbolic machine keeps in memory. Everything that can be is kept
+// Linear, so the tree only gets deeper where it has to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Linear(Linear),
+    // Only ever used for sums and products that aren't linear.
+    Add(Rc<Expr>, Rc<Expr>),
+    Multiply(Rc<Expr>, Rc<Expr>),
+    LessThan(Rc<Expr>, Rc<Expr>),
+    Equals(Rc<Expr>, Rc<Expr>),
+    // Whatever's in memory at an address that depends on a variable.
+    Load(Rc<Expr>)
+}
+
+impl Expr {
+    pub fn constant(value: Value) -> Expr {
+        Expr::Linear(Linear { constant: value, terms: BTreeMap::new() })
+    }
+
+    pub fn variable(name: &str) -> Expr {
+        let terms = vec![(name.to_string(), 1)].into_iter().collect();
+        Expr::Linear(Linear { constant: 0, terms })
+    }
+
+    pub fn as_constant(&self) -> Option<Value> {
+        match self {
+            Expr::Linear(linear) if linear.terms.is_empty() => Some(linear.constant),
+            _ => None
+        }
+    }
+
+    pub fn as_linear(&self) -> Option<&Linear> {
+        match self {
+            Expr::Linear(linear) => Some(linear),
+            _ => None
+        }
+    }
+
+    // Values for each of the variables, in the order given, that make this
+    // come out as target. The first solution in that order is the one
+    // returned, or None if there aren't any. Every variable in the
+    // expression needs a range, and each range but the last gets searched
+    // through, so they shouldn't be huge.
+    pub fn solve(&self, target: Value, ranges: &[(&str, RangeInclusive<Value>)])
+                 -> Result<Option<Vec<Value>>, SymbolicError> {
+        let linear = self.as_linear().ok_or_else(|| SymbolicError::NotLinear(self.to_string()))?;
+        if let Some(unbound) = linear.terms.keys().find(|v| !ranges.iter().any(|(r, _)| r == v)) {
+            return Err(SymbolicError::Unbound(unbound.clone()));
+        }
+
+        let terms: Vec<(i128, RangeInclusive<i128>)> = ranges.iter()
+            .map(|(variable, range)| {
+                (linear.coefficient(variable) as i128, *range.start() as i128..=*range.end() as i128)
+            })
+            .collect();
+        let remaining = target as i128 - linear.constant as i128;
+
+        let mut solution = Vec::new();
+        if search(&terms, remaining, &mut solution) {
+            Ok(Some(solution.into_iter().map(|value| value as Value).collect()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Arithmetic on two constants that overflows is an error just like it
+    // is in a Machine, but anything involving variables just stops being
+    // Linear.
+    fn arithmetic(opcode: Opcode, a: Rc<Expr>, b: Rc<Expr>, pointer: Address)
+                  -> Result<Rc<Expr>, OperationalError> {
+        if let (Some(left), Some(right)) = (a.as_constant(), b.as_constant()) {
+            let result = match opcode {
+                Opcode::Multiply => left.checked_mul(right),
+                _ => left.checked_add(right)
+            };
+            return result.map(|value| Rc::new(Expr::constant(value)))
+                .ok_or(OperationalError::ArithmeticOverflow {
+                    opcode, left: left as i128, right: right as i128, pointer
+                });
+        }
+
+        let linear = match (opcode, a.as_linear(), b.as_linear(), a.as_constant(), b.as_constant()) {
+            (Opcode::Add, Some(left), Some(right), _, _) => left.add(right),
+            (Opcode::Multiply, Some(left), _, _, Some(factor)) => left.scale(factor),
+            (Opcode::Multiply, _, Some(right), Some(factor), _) => right.scale(factor),
+            _ => None
+        };
+
+        Ok(Rc::new(match linear {
+            Some(linear) => Expr::Linear(linear),
+            None if opcode == Opcode::Multiply => Expr::Multiply(a, b),
+            None => Expr::Add(a, b)
+        }))
+    }
+
+    fn compare(opcode: Opcode, a: Rc<Expr>, b: Rc<Expr>) -> Rc<Expr> {
+        Rc::new(match (opcode, a.as_constant(), b.as_constant()) {
+            (Opcode::LessThan, Some(left), Some(right)) => Expr::constant((left < right) as Value),
+            (_, Some(left), Some(right)) => Expr::constant((left == right) as Value),
+            (Opcode::LessThan, _, _) => Expr::LessThan(a, b),
+            _ => Expr::Equals(a, b)
+        })
+    }
+}
+
+// Tries every value of the first variable that leaves a reachable target for
+// the rest, and works the last one out directly.
+fn search(terms: &[(i128, RangeInclusive<i128>)], remaining: i128, solution: &mut Vec<i128>) -> bool {
+    let ((coefficient, range), rest) = match terms.split_first() {
+        Some(first) => first,
+        None => return remaining == 0
+    };
+
+    if rest.is_empty() {
+        let value = match coefficient {
+            0 if remaining == 0 => *range.start(),
+            0 => return false,
+            c if remaining % c != 0 => return false,
+            c => remaining / c
+        };
+        if range.contains(&value) {
+            solution.push(value);
+            return true;
+        }
+        return false;
+    }
+
+    // The smallest and biggest the rest of the terms can add up to.
+    let (low, high) = rest.iter().fold((0, 0), |(low, high), (c, range)| {
+        let (a, b) = (c * range.start(), c * range.end());
+        (low + a.min(b), high + a.max(b))
+    });
+
+    for value in range.clone() {
+        let left = remaining - coefficient * value;
+        if left < low || left > high {
+            continue;
+        }
+        solution.push(value);
+        if search(rest, left, solution) {
+            return true;
+        }
+        solution.pop();
+    }
+    false
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Expr::Linear(linear) => write!(f, "{}", linear),
+            Expr::Add(a, b) => write!(f, "({}) + ({})", a, b),
+            Expr::Multiply(a, b) => write!(f, "({}) * ({})", a, b),
+            Expr::LessThan(a, b) => write!(f, "({}) < ({})", a, b),
+            Expr::Equals(a, b) => write!(f, "({}) == ({})", a, b),
+            Expr::Load(address) => write!(f, "m[{}]", address)
+        }
+    }
+}
+
+impl From<Value> for Expr {
+    fn from(value: Value) -> Expr {
+        Expr::constant(value)
+    }
+}
+
+// Runs a program with some of its memory or input standing in for numbers
+// we don't know yet, building up expressions in them instead of numbers.
+// That only works as long as the program doesn't need to know them: jumping
+// on them, writing to addresses worked out from them and so on stop with
+// SymbolicError::Undecidable. Reading from an address worked out from them
+// is fine, but what's read is a Load that nothing can be solved for.
+#[derive(Debug, Clone)]
+pub struct SymbolicMachine {
+    memory: Vec<Rc<Expr>>,
+    pointer: Address,
+    relative_base: Value,
+    state: MachineState,
+    input: VecDeque<Rc<Expr>>,
+    output: Vec<Rc<Expr>>
+}
+
+impl SymbolicMachine {
+    pub fn new(slots: &[Value]) -> SymbolicMachine {
+        SymbolicMachine {
+            memory: slots.iter().map(|&value| Rc::new(Expr::constant(value))).collect(),
+            pointer: 0,
+            relative_base: 0,
+            state: MachineState::Running,
+            input: VecDeque::new(),
+            output: Vec::new()
+        }
+    }
+
+    pub fn get(&self, address: Address) -> Expr {
+        self.memory.get(address).map_or(Expr::constant(0), |value| (**value).clone())
+    }
+
+    pub fn set(&mut self, address: Address, value: Expr) -> Result<(), OperationalError> {
+        self.store(address, Rc::new(value))
+    }
+
+    pub fn write(&mut self, input: Expr) {
+        self.input.push_back(Rc::new(input));
+        if self.state == MachineState::Blocked {
+            self.state = MachineState::Running;
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<Expr> {
+        self.output.drain(..).map(|value| (*value).clone()).collect()
+    }
+
+    pub fn state(&self) -> MachineState {
+        self.state
+    }
+
+    pub fn pointer(&self) -> Address {
+        self.pointer
+    }
+
+    pub fn run(&mut self) -> Result<RunOutcome, SymbolicError> {
+        self.run_limited(None)
+    }
+
+    // Like Machine::run_for, since a loop on something we do know can still
+    // go on forever.
+    pub fn run_for(&mut self, max_instructions: usize) -> Result<RunOutcome, SymbolicError> {
+        self.run_limited(Some(max_instructions))
+    }
+
+    fn run_limited(&mut self, budget: Option<usize>) -> Result<RunOutcome, SymbolicError> {
+        let mut executed = 0;
+        loop {
+            match self.state {
+                MachineState::Halted => return Ok(RunOutcome::Halted),
+                MachineState::Blocked => return Ok(RunOutcome::Blocked),
+                MachineState::Running => {}
+            }
+            if budget == Some(executed) {
+                return Ok(RunOutcome::BudgetExhausted);
+            }
+            self.execute_next()?;
+            executed += 1;
+        }
+    }
+
+    fn load(&self, address: Address) -> Result<Rc<Expr>, OperationalError> {
+        if address >= DEFAULT_MEMORY_LIMIT {
+            return Err(OperationalError::MemoryLimitExceeded { address, limit: DEFAULT_MEMORY_LIMIT });
+        }
+        Ok(self.memory.get(address).cloned().unwrap_or_else(|| Rc::new(Expr::constant(0))))
+    }
+
+    fn store(&mut self, address: Address, value: Rc<Expr>) -> Result<(), OperationalError> {
+        if address >= DEFAULT_MEMORY_LIMIT {
+            return Err(OperationalError::MemoryLimitExceeded { address, limit: DEFAULT_MEMORY_LIMIT });
+        }
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, Rc::new(Expr::constant(0)));
+        }
+        self.memory[address] = value;
+        Ok(())
+    }
+
+    fn known(&self, value: &Rc<Expr>) -> Result<Value, SymbolicError> {
+        value.as_constant().ok_or_else(|| SymbolicError::Undecidable {
+            address: self.pointer,
+            value: value.to_string()
+        })
+    }
+
+    // The address a parameter points at, None if it's immediate.
+    fn address(&self, mode: ParameterMode, parameter: &Rc<Expr>) -> Result<Option<Rc<Expr>>, SymbolicError> {
+        Ok(match mode {
+            ParameterMode::Immediate => None,
+            ParameterMode::Positional => Some(parameter.clone()),
+            ParameterMode::Relative => {
+                let base = Rc::new(Expr::constant(self.relative_base));
+                Some(Expr::arithmetic(Opcode::Add, base, parameter.clone(), self.pointer)?)
+            }
+        })
+    }
+
+    fn value(&self, mode: ParameterMode, parameter: &Rc<Expr>) -> Result<Rc<Expr>, SymbolicError> {
+        match self.address(mode, parameter)? {
+            None => Ok(parameter.clone()),
+            Some(address) => match address.as_constant() {
+                Some(address) => Ok(self.load(address.into_addr()?)?),
+                None => Ok(Rc::new(Expr::Load(address)))
+            }
+        }
+    }
+
+    fn destination(&self, mode: ParameterMode, parameter: &Rc<Expr>) -> Result<Address, SymbolicError> {
+        match self.address(mode, parameter)? {
+            None => Err(OperationalError::ImmediateModeStorage.into()),
+            Some(address) => Ok(self.known(&address)?.into_addr()?)
+        }
+    }
+
+    fn execute_next(&mut self) -> Result<(), SymbolicError> {
+        let word = self.known(&self.load(self.pointer)?)?;
+        let (opcode, modes) = Instruction::op_and_mode_digits(&word)?;
+        let parameters = (0..opcode.parameter_count())
+            .map(|i| self.load(self.pointer + i + 1))
+            .collect::<Result<Vec<Rc<Expr>>, OperationalError>>()?;
+        let value = |i: usize| self.value(modes[i], &parameters[i]);
+        let mut next = self.pointer + opcode.parameter_count() + 1;
+
+        match opcode {
+            Opcode::Add | Opcode::Multiply => {
+                let result = Expr::arithmetic(opcode, value(0)?, value(1)?, self.pointer)?;
+                let destination = self.destination(modes[2], &parameters[2])?;
+                self.store(destination, result)?;
+            },
+            Opcode::LessThan | Opcode::Equals => {
+                let result = Expr::compare(opcode, value(0)?, value(1)?);
+                let destination = self.destination(modes[2], &parameters[2])?;
+                self.store(destination, result)?;
+            },
+            Opcode::Input => {
+                let destination = self.destination(modes[0], &parameters[0])?;
+                match self.input.pop_front() {
+                    Some(input) => self.store(destination, input)?,
+                    None => {
+                        self.state = MachineState::Blocked;
+                        return Ok(());
+                    }
+                }
+            },
+            Opcode::Output => {
+                let output = value(0)?;
+                self.output.push(output);
+            },
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let condition = self.known(&value(0)?)?;
+                if (condition != 0) == (opcode == Opcode::JumpIfTrue) {
+                    next = self.known(&value(1)?)?.into_addr()?;
+                }
+            },
+            Opcode::RelativeBaseOffset => {
+                let change = self.known(&value(0)?)?;
+                self.relative_base = self.relative_base.checked_add(change)
+                    .ok_or(OperationalError::ArithmeticOverflow {
+                        opcode,
+                        left: self.relative_base as i128,
+                        right: change as i128,
+                        pointer: self.pointer
+                    })?;
+            },
+            Opcode::Halt => {
+                self.state = MachineState::Halted;
+                return Ok(());
+            }
+        }
+
+        self.pointer = next;
+        Ok(())
+    }
+}
+
+impl Machine {
+    // A symbolic machine that starts where this one is, with everything it
+    // has in memory and its pending input. Symbolic machines can't go past
+    // DEFAULT_MEMORY_LIMIT, so anything paged memory has beyond that is
+    // left behind, and using it is a MemoryLimitExceeded error like always.
+    pub fn symbolic(&self) -> SymbolicMachine {
+        let end = self.slots.used_end(DEFAULT_MEMORY_LIMIT);
+        let mut symbolic = SymbolicMachine::new(&self.slots.to_vec_upto(end));
+        symbolic.pointer = self.pointer;
+        symbolic.relative_base = self.relative_base;
+        symbolic.state = self.state;
+        for value in self.pending_input() {
+            symbolic.write(Expr::constant(*value));
+        }
+        symbolic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_programs() -> Result<(), SymbolicError> {
+        // [result] = 3x + y + 7
+        let mut machine = Machine::from_assembly("
+                    MUL  [x], #3, [result]
+                    ADD  [result], [y], [result]
+                    ADD  [result], #7, [result]
+                    HLT
+            x:      DATA 0
+            y:      DATA 0
+            result: DATA 0
+        ").unwrap().symbolic();
+        machine.set(13, Expr::variable("x"))?;
+        machine.set(14, Expr::variable("y"))?;
+
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        let result = machine.get(15);
+        assert_eq!("3*x + y + 7", result.to_string());
+        assert_eq!(Some(vec![3, 8]), result.solve(24, &[("x", 0..=9), ("y", 0..=9)])?);
+        assert_eq!(None, result.solve(100, &[("x", 0..=9), ("y", 0..=9)])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_input() -> Result<(), SymbolicError> {
+        let mut machine = Machine::from_slots(vec![3, 9, 1002, 9, -2, 9, 4, 9, 99, 0]).symbolic();
+        assert_eq!(RunOutcome::Blocked, machine.run()?);
+
+        machine.write(Expr::variable("a"));
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        let output = machine.read();
+        assert_eq!("-2*a", output[0].to_string());
+        assert_eq!(Some(vec![-21]), output[0].solve(42, &[("a", -100..=100)])?);
+        assert!(matches!(output[0].solve(42, &[]), Err(SymbolicError::Unbound(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn day_two_shaped() -> Result<(), SymbolicError> {
+        // Like the real inputs, the first instruction adds whatever noun and
+        // verb point at, but that's overwritten before anything uses it.
+        let slots = vec![1, 0, 0, 3, 1002, 1, 100, 3, 1, 3, 2, 0, 99];
+        let mut machine = Machine::from_slots(slots.clone()).symbolic();
+        machine.set(1, Expr::variable("noun"))?;
+        machine.set(2, Expr::variable("verb"))?;
+        machine.run()?;
+
+        let solution = machine.get(0).solve(1234, &[("noun", 0..=99), ("verb", 0..=99)])?;
+        assert_eq!(Some(vec![12, 34]), solution);
+
+        let mut concrete = Machine::from_slots(slots);
+        concrete.set(1, 12)?;
+        concrete.set(2, 34)?;
+        concrete.run()?;
+        assert_eq!(1234, *concrete.get(0)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn undecidable() -> Result<(), SymbolicError> {
+        // Jumps on its input.
+        let mut machine = Machine::from_slots(vec![3, 7, 1005, 7, 0, 99, 99, 0]).symbolic();
+        machine.write(Expr::variable("a"));
+        assert!(matches!(machine.run(), Err(SymbolicError::Undecidable { address: 2, .. })));
+
+        // The day 2 example reads from the addresses being solved for.
+        let mut machine = Machine::from_slots(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]).symbolic();
+        machine.set(1, Expr::variable("noun"))?;
+        machine.set(2, Expr::variable("verb"))?;
+        machine.run()?;
+        assert_eq!("((m[noun]) + (m[verb])) * (50)", machine.get(0).to_string());
+        assert!(matches!(machine.get(0).solve(3500, &[("noun", 0..=11), ("verb", 0..=11)]),
+                         Err(SymbolicError::NotLinear(_))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,467 @@
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use super::{Machine, MachineState, OperationalError, RunOutcome, Value};
+use super::number::Number;
+
+// When running on threads, how many instructions each machine runs between
+// checks for whether it's been told to stop, and how long a blocked one waits
+// for input between checks.
+const THREAD_BUDGET: usize = 10_000;
+const THREAD_POLL: Duration = Duration::from_millis(5);
+
+pub type NodeId = usize;
+
+#[derive(Debug, Error)]
+pub enum TopologyError {
+    #[error("Node {node} failed: {error}")]
+    Machine { node: NodeId, error: OperationalError }
+}
+
+// Why a call to Topology::run returned. Either way nothing else can happen
+// until someone writes more input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopologyOutcome {
+    Halted,
+    // These nodes are all blocked waiting for input that nothing is ever
+    // going to send them. Every other node has halted.
+    Deadlocked(Vec<NodeId>),
+    // Only from run_threaded. These nodes hadn't halted when time ran out.
+    TimedOut(Vec<NodeId>)
+}
+
+#[derive(Debug, Clone)]
+struct Node<V> {
+    machine: Machine<V>,
+    successors: Vec<NodeId>,
+    // Everything this node has ever output, whether or not anything was
+    // listening.
+    outputs: Vec<V>
+}
+
+// Machines wired together so that each one's output becomes the input of
+// every machine it's connected to. Any shape works: a node connected to
+// several others sends each of them a copy of every value, a node with
+// several connected to it gets their values interleaved in the order they're
+// produced, and cycles are fine.
+//
+// Running goes round the nodes in the order they were added, running each
+// until it blocks or halts and passing its output along straight away, until
+// a whole pass goes by where none of them can do anything.
+#[derive(Debug, Clone)]
+pub struct Topology<V = Value> {
+    nodes: Vec<Node<V>>
+}
+
+impl<V: Number> Default for Topology<V> {
+    fn default() -> Self {
+        Topology { nodes: Vec::new() }
+    }
+}
+
+impl<V: Number> Topology<V> {
+    pub fn new() -> Topology<V> {
+        Topology::default()
+    }
+
+    // Each machine connected to the next.
+    pub fn pipeline(machines: Vec<Machine<V>>) -> Topology<V> {
+        let mut topology = Topology::new();
+        let nodes: Vec<NodeId> = machines.into_iter().map(|m| topology.add(m)).collect();
+        for pair in nodes.windows(2) {
+            topology.connect(pair[0], pair[1]);
+        }
+        topology
+    }
+
+    // A pipeline with the last machine connected back round to the first.
+    pub fn ring(machines: Vec<Machine<V>>) -> Topology<V> {
+        let mut topology = Topology::pipeline(machines);
+        if !topology.nodes.is_empty() {
+            topology.connect(topology.nodes.len() - 1, 0);
+        }
+        topology
+    }
+
+    pub fn add(&mut self, machine: Machine<V>) -> NodeId {
+        self.nodes.push(Node { machine, successors: Vec::new(), outputs: Vec::new() });
+        self.nodes.len() - 1
+    }
+
+    // Panics if either node doesn't exist, the same as indexing a Vec.
+    pub fn connect(&mut self, from: NodeId, to: NodeId) {
+        assert!(to < self.nodes.len(), "node {} doesn't exist", to);
+        self.nodes[from].successors.push(to);
+    }
+
+    // Input from outside the topology, like the first signal into day 7's
+    // amplifiers.
+    pub fn write(&mut self, node: NodeId, value: V) {
+        self.nodes[node].machine.write(value);
+    }
+
+    pub fn run(&mut self) -> Result<TopologyOutcome, TopologyError> {
+        loop {
+            let mut progress = false;
+
+            for node in 0..self.nodes.len() {
+                let machine = &mut self.nodes[node].machine;
+                let before = machine.instruction_counter();
+                machine.run().map_err(|error| TopologyError::Machine { node, error })?;
+                progress |= machine.instruction_counter() != before;
+
+                let output = machine.read();
+                for &successor in &self.nodes[node].successors.clone() {
+                    for value in &output {
+                        self.nodes[successor].machine.write(value.clone());
+                    }
+                }
+                self.nodes[node].outputs.extend(output);
+            }
+
+            if !progress {
+                break;
+            }
+        }
+
+        let blocked: Vec<NodeId> = (0..self.nodes.len())
+            .filter(|&node| self.nodes[node].machine.state() != MachineState::Halted)
+            .collect();
+        if blocked.is_empty() {
+            Ok(TopologyOutcome::Halted)
+        } else {
+            Ok(TopologyOutcome::Deadlocked(blocked))
+        }
+    }
+
+    // Like run, but with every machine on its own thread and values passed
+    // between them over channels. Nodes with only one node connected to them
+    // see exactly the same input as with run. Nodes with several get the
+    // same values, but interleaved in whatever order the threads get to them.
+    //
+    // Gives up once timeout has passed, leaving every machine however far
+    // it got. Deadlocks are noticed as soon as every machine is blocked or
+    // halted with nothing on its way to any of the blocked ones.
+    pub fn run_threaded(&mut self, timeout: Duration) -> Result<TopologyOutcome, TopologyError> {
+        let size = self.nodes.len();
+        let (senders, receivers): (Vec<Sender<V>>, Vec<Receiver<V>>) =
+            (0..size).map(|_| mpsc::channel()).unzip();
+        let monitor = Monitor {
+            state: Mutex::new(MonitorState {
+                statuses: vec![NodeStatus::Running; size],
+                in_flight: vec![0; size]
+            }),
+            changed: Condvar::new()
+        };
+        let stop = AtomicBool::new(false);
+        let deadline = Instant::now() + timeout;
+
+        let (outcome, results) = thread::scope(|scope| {
+            let handles: Vec<_> = self.nodes.iter_mut()
+                .zip(receivers)
+                .enumerate()
+                .map(|(id, (node, input))| {
+                    let successors = node.successors.iter()
+                        .map(|&successor| (successor, senders[successor].clone()))
+                        .collect();
+                    let (monitor, stop) = (&monitor, &stop);
+                    scope.spawn(move || {
+                        let result = drive(id, node, &input, successors, monitor, stop);
+                        (result, input)
+                    })
+                })
+                .collect();
+
+            let mut state = monitor.state.lock().unwrap();
+            let outcome = loop {
+                if state.statuses.contains(&NodeStatus::Failed) {
+                    break None;
+                }
+                if let Some(outcome) = state.settled() {
+                    break Some(outcome);
+                }
+                let now = Instant::now();
+                if now >= deadline {
+                    break Some(TopologyOutcome::TimedOut((0..size)
+                        .filter(|&node| state.statuses[node] != NodeStatus::Halted)
+                        .collect()));
+                }
+                state = monitor.changed.wait_timeout(state, deadline - now).unwrap().0;
+            };
+            drop(state);
+
+            stop.store(true, Ordering::SeqCst);
+            let results: Vec<_> = handles.into_iter()
+                .map(|handle| handle.join().expect("machine thread panicked"))
+                .collect();
+            (outcome, results)
+        });
+
+        // Anything sent to a machine that stopped before reading it is
+        // written to it, the same as run would have.
+        for (node, (result, input)) in results.into_iter().enumerate() {
+            result?;
+            for value in input.try_iter() {
+                self.nodes[node].machine.write(value);
+            }
+        }
+
+        // A failed node always has an error to return above.
+        Ok(outcome.unwrap())
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn machine(&self, node: NodeId) -> &Machine<V> {
+        &self.nodes[node].machine
+    }
+
+    pub fn outputs(&self, node: NodeId) -> &[V] {
+        &self.nodes[node].outputs
+    }
+
+    pub fn last_output(&self, node: NodeId) -> Option<&V> {
+        self.nodes[node].outputs.last()
+    }
+
+    pub fn into_machines(self) -> Vec<Machine<V>> {
+        self.nodes.into_iter().map(|node| node.machine).collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NodeStatus { Running, Blocked, Halted, Failed }
+
+#[derive(Debug)]
+struct MonitorState {
+    statuses: Vec<NodeStatus>,
+    // Values sent to each node that it hasn't received yet.
+    in_flight: Vec<usize>
+}
+
+impl MonitorState {
+    // Nothing more can happen once every node is halted or blocked with
+    // nothing coming. Values sent to halted nodes don't count since they'll
+    // never be read.
+    fn settled(&self) -> Option<TopologyOutcome> {
+        let mut blocked = Vec::new();
+        for (node, status) in self.statuses.iter().enumerate() {
+            match status {
+                NodeStatus::Halted => {},
+                NodeStatus::Blocked if self.in_flight[node] == 0 => blocked.push(node),
+                _ => return None
+            }
+        }
+
+        if blocked.is_empty() {
+            Some(TopologyOutcome::Halted)
+        } else {
+            Some(TopologyOutcome::Deadlocked(blocked))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Monitor {
+    state: Mutex<MonitorState>,
+    changed: Condvar
+}
+
+impl Monitor {
+    fn update<F: FnOnce(&mut MonitorState)>(&self, f: F) {
+        f(&mut self.state.lock().unwrap());
+        self.changed.notify_all();
+    }
+}
+
+// Runs one node of run_threaded until it halts, fails or is told to stop.
+fn drive<V: Number>(id: NodeId,
+                    node: &mut Node<V>,
+                    input: &Receiver<V>,
+                    successors: Vec<(NodeId, Sender<V>)>,
+                    monitor: &Monitor,
+                    stop: &AtomicBool) -> Result<(), TopologyError> {
+    let receive = |machine: &mut Machine<V>, value: V| {
+        monitor.update(|state| {
+            state.in_flight[id] -= 1;
+            state.statuses[id] = NodeStatus::Running;
+        });
+        machine.write(value);
+    };
+
+    while !stop.load(Ordering::SeqCst) {
+        let outcome = node.machine.run_for(THREAD_BUDGET);
+
+        // Counted as in flight before it's sent so that nobody can see the
+        // receiver blocked with nothing coming in between.
+        let output = node.machine.read();
+        monitor.update(|state| {
+            for (successor, _) in &successors {
+                state.in_flight[*successor] += output.len();
+            }
+        });
+        for value in &output {
+            for (_, sender) in &successors {
+                // The receiving thread only goes away once we're stopping.
+                let _ = sender.send(value.clone());
+            }
+        }
+        node.outputs.extend(output);
+
+        match outcome {
+            Err(error) => {
+                monitor.update(|state| state.statuses[id] = NodeStatus::Failed);
+                return Err(TopologyError::Machine { node: id, error });
+            },
+            Ok(RunOutcome::Halted) => {
+                monitor.update(|state| state.statuses[id] = NodeStatus::Halted);
+                return Ok(());
+            },
+            Ok(RunOutcome::Blocked) => {
+                monitor.update(|state| state.statuses[id] = NodeStatus::Blocked);
+                loop {
+                    match input.recv_timeout(THREAD_POLL) {
+                        Ok(value) => {
+                            receive(&mut node.machine, value);
+                            break;
+                        },
+                        Err(RecvTimeoutError::Timeout) if !stop.load(Ordering::SeqCst) => {},
+                        Err(_) => return Ok(())
+                    }
+                }
+                for value in input.try_iter() {
+                    receive(&mut node.machine, value);
+                }
+            },
+            Ok(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amplifiers(program: &str, settings: &[Value]) -> Vec<Machine> {
+        let program = Machine::from_str(program).unwrap();
+        settings.iter()
+            .map(|&setting| {
+                let mut machine = program.clone();
+                machine.write(setting);
+                machine
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pipelines() -> Result<(), TopologyError> {
+        let program = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let mut topology = Topology::pipeline(amplifiers(program, &[4, 3, 2, 1, 0]));
+        topology.write(0, 0);
+
+        assert_eq!(TopologyOutcome::Halted, topology.run()?);
+        assert_eq!(Some(&43210), topology.last_output(4));
+        assert_eq!(&[4], topology.outputs(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn rings() -> Result<(), TopologyError> {
+        let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+                       1005,28,6,99,0,0,5";
+        let mut topology = Topology::ring(amplifiers(program, &[9, 8, 7, 6, 5]));
+        topology.write(0, 0);
+
+        assert_eq!(TopologyOutcome::Halted, topology.run()?);
+        assert_eq!(Some(&139629729), topology.last_output(4));
+
+        Ok(())
+    }
+
+    #[test]
+    fn fan_out_and_in() -> Result<(), TopologyError> {
+        let mut topology = Topology::new();
+        let source = topology.add(Machine::from_slots(vec![104, 3, 99]));
+        let double = topology.add(Machine::from_slots(vec![3, 9, 1002, 9, 2, 9, 4, 9, 99, 0]));
+        let tenfold = topology.add(Machine::from_slots(vec![3, 9, 1002, 9, 10, 9, 4, 9, 99, 0]));
+        let sum = topology.add(Machine::from_slots(vec![3, 11, 3, 12, 1, 11, 12, 11, 4, 11, 99, 0, 0]));
+        topology.connect(source, double);
+        topology.connect(source, tenfold);
+        topology.connect(double, sum);
+        topology.connect(tenfold, sum);
+
+        assert_eq!(TopologyOutcome::Halted, topology.run()?);
+        assert_eq!(&[36], topology.outputs(sum));
+
+        Ok(())
+    }
+
+    #[test]
+    fn deadlock() -> Result<(), TopologyError> {
+        // Three machines that each wait for input before saying anything,
+        // two of them waiting on each other.
+        let echo = Machine::from_slots(vec![3, 5, 4, 5, 99, 0]);
+        let mut topology = Topology::new();
+        let a = topology.add(echo.clone());
+        let b = topology.add(echo.clone());
+        let c = topology.add(echo);
+        topology.connect(a, b);
+        topology.connect(b, a);
+        topology.connect(c, a);
+
+        assert_eq!(TopologyOutcome::Deadlocked(vec![a, b, c]), topology.run()?);
+
+        // Getting c going gets the rest going too.
+        topology.write(c, 7);
+        assert_eq!(TopologyOutcome::Halted, topology.run()?);
+        assert_eq!(&[7], topology.outputs(b));
+
+        Ok(())
+    }
+
+    #[test]
+    fn threads() -> Result<(), TopologyError> {
+        let program = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+                       1005,28,6,99,0,0,5";
+        let mut topology = Topology::ring(amplifiers(program, &[9, 8, 7, 6, 5]));
+        topology.write(0, 0);
+        assert_eq!(TopologyOutcome::Halted, topology.run_threaded(Duration::from_secs(10))?);
+        assert_eq!(Some(&139629729), topology.last_output(4));
+
+        let echo = Machine::from_slots(vec![3, 5, 4, 5, 99, 0]);
+        let mut topology = Topology::ring(vec![echo.clone(), echo]);
+        assert_eq!(TopologyOutcome::Deadlocked(vec![0, 1]),
+                   topology.run_threaded(Duration::from_secs(10))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn threads_time_out() -> Result<(), TopologyError> {
+        // The second machine spins forever once it's got its input.
+        let mut topology = Topology::pipeline(vec![
+            Machine::from_slots(vec![104, 1, 99]),
+            Machine::from_slots(vec![3, 7, 1105, 1, 2, 99, 0, 0])
+        ]);
+        assert_eq!(TopologyOutcome::TimedOut(vec![1]),
+                   topology.run_threaded(Duration::from_millis(50))?);
+        assert_eq!(MachineState::Running, topology.machine(1).state());
+        assert!(topology.machine(1).pending_input().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use super::{Address, Value, Instruction};
+use super::number::Number;
+use super::disassembler;
+
+// Everything one executed instruction did. Values are the resolved value of
+// each parameter the instruction reads, in order, so the address an Add
+// stores into shows up in write rather than values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent<V = Value> {
+    pub counter: usize,
+    pub address: Address,
+    pub instruction: Instruction<V>,
+    pub values: Vec<V>,
+    pub write: Option<(Address, V)>,
+    // The new relative base, only if the instruction changed it.
+    pub relative_base: Option<V>
+}
+
+impl<V: Number> std::fmt::Display for TraceEvent<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut effects = String::new();
+        if !self.values.is_empty() {
+            let values: Vec<String> = self.values.iter().map(|v| v.to_string()).collect();
+            effects.push_str(&format!(" ; {}", values.join(", ")));
+        }
+        if let Some((address, value)) = &self.write {
+            effects.push_str(&format!(" -> [{}] = {}", address, value));
+        }
+        if let Some(relative_base) = &self.relative_base {
+            effects.push_str(&format!(" -> rb = {}", relative_base));
+        }
+
+        let instruction = self.instruction.to_string();
+        if effects.is_empty() {
+            write!(f, "{:>8} {:>5}: {}", self.counter, self.address, instruction)
+        } else {
+            write!(f, "{:>8} {:>5}: {:<24}{}", self.counter, self.address, instruction, effects)
+        }
+    }
+}
+
+// The instruction a machine failed on, which never gets a TraceEvent since
+// it never finished. instruction is None if it didn't even decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault<V = Value> {
+    pub counter: usize,
+    pub address: Address,
+    pub instruction: Option<Instruction<V>>,
+    pub error: String
+}
+
+impl<V: Number> std::fmt::Display for Fault<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let instruction = self.instruction.as_ref().map_or("???".to_string(), |i| i.to_string());
+        write!(f, "{:>8} {:>5}: {:<24} ; failed: {}", self.counter, self.address, instruction, self.error)
+    }
+}
+
+pub trait TraceSink<V = Value>: Send {
+    fn record(&mut self, event: &TraceEvent<V>);
+
+    // Called instead of record for the instruction a run fails on.
+    fn fault(&mut self, _fault: &Fault<V>) {}
+
+    // Called when the sink is taken off a machine.
+    fn flush(&mut self) {}
+}
+
+// A shared handle to a sink. The caller keeps a clone of the Arc so that it
+// can get at whatever the sink collected once the machine stops, including
+// after an OperationalError.
+#[derive(Clone)]
+pub struct Tracer<V = Value>(pub Arc<Mutex<dyn TraceSink<V>>>);
+
+impl<V> Tracer<V> {
+    pub fn record(&self, event: &TraceEvent<V>) {
+        // A sink that panicked while recording has nothing useful left to
+        // say, so there's no point poisoning the machine over it.
+        if let Ok(mut sink) = self.0.lock() {
+            sink.record(event);
+        }
+    }
+
+    pub fn fault(&self, fault: &Fault<V>) {
+        if let Ok(mut sink) = self.0.lock() {
+            sink.fault(fault);
+        }
+    }
+}
+
+// Every machine tracing to a sink has its own Tracer, so this is whenever one
+// stops tracing, including by being dropped. Flushing more than once doesn't
+// hurt.
+impl<V> Drop for Tracer<V> {
+    fn drop(&mut self) {
+        if let Ok(mut sink) = self.0.lock() {
+            sink.flush();
+        }
+    }
+}
+
+impl<V> std::fmt::Debug for Tracer<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Tracer")
+    }
+}
+
+// One line per instruction, in the same shape as the disassembler listing.
+pub struct TextSink<W: Write + Send> {
+    writer: W,
+    error: Option<io::Error>
+}
+
+impl<W: Write + Send> TextSink<W> {
+    pub fn new(writer: W) -> Self {
+        TextSink { writer, error: None }
+    }
+
+    // The first write error, if any. Nothing is written after one.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send> TextSink<W> {
+    fn write<F: FnOnce(&mut W) -> io::Result<()>>(&mut self, write: F) {
+        if self.error.is_none() {
+            if let Err(e) = write(&mut self.writer) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl<W: Write + Send, V: Number> TraceSink<V> for TextSink<W> {
+    fn record(&mut self, event: &TraceEvent<V>) {
+        self.write(|writer| writeln!(writer, "{}", event));
+    }
+
+    fn fault(&mut self, fault: &Fault<V>) {
+        self.write(|writer| writeln!(writer, "{}", fault));
+    }
+
+    fn flush(&mut self) {
+        self.write(|writer| writer.flush());
+    }
+}
+
+// Each event is written as:
+//
+//     counter: u64, address: u64,
+//     word count: u8, words: i64 each (the raw instruction and parameters),
+//     value count: u8, values: i64 each,
+//     flags: u8 (bit 0 for a write, bit 1 for a relative base change),
+//     then the write address as u64 and value as i64, and the new relative
+//     base as i64, if flagged.
+//
+// All little endian. The instruction is stored raw and decoded again on the
+// way back in, since that's smaller than storing the decoded form. Since
+// everything is a fixed 64 bits this only works for machines over Value.
+// Faults aren't written, the error that caused them goes back to whoever ran
+// the machine anyway.
+pub struct BinarySink<W: Write + Send> {
+    writer: W,
+    error: Option<io::Error>
+}
+
+const WRITE_FLAG: u8 = 1;
+const RELATIVE_BASE_FLAG: u8 = 2;
+
+impl<W: Write + Send> BinarySink<W> {
+    pub fn new(writer: W) -> Self {
+        BinarySink { writer, error: None }
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_event(&mut self, event: &TraceEvent) -> io::Result<()> {
+        let mut words = vec![event.instruction.encode()];
+        words.extend(event.instruction.parameters().iter().map(|p| p.value));
+
+        let mut bytes = Vec::with_capacity(64);
+        bytes.extend_from_slice(&(event.counter as u64).to_le_bytes());
+        bytes.extend_from_slice(&(event.address as u64).to_le_bytes());
+        bytes.push(words.len() as u8);
+        for word in words {
+            bytes.extend_from_slice(&(word as i64).to_le_bytes());
+        }
+        bytes.push(event.values.len() as u8);
+        for value in &event.values {
+            bytes.extend_from_slice(&(*value as i64).to_le_bytes());
+        }
+
+        let mut flags = 0;
+        if event.write.is_some() {
+            flags |= WRITE_FLAG;
+        }
+        if event.relative_base.is_some() {
+            flags |= RELATIVE_BASE_FLAG;
+        }
+        bytes.push(flags);
+
+        if let Some((address, value)) = event.write {
+            bytes.extend_from_slice(&(address as u64).to_le_bytes());
+            bytes.extend_from_slice(&(value as i64).to_le_bytes());
+        }
+        if let Some(relative_base) = event.relative_base {
+            bytes.extend_from_slice(&(relative_base as i64).to_le_bytes());
+        }
+
+        self.writer.write_all(&bytes)
+    }
+}
+
+impl<W: Write + Send> TraceSink for BinarySink<W> {
+    fn record(&mut self, event: &TraceEvent) {
+        if self.error.is_none() {
+            if let Err(e) = self.write_event(event) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.error.is_none() {
+            if let Err(e) = self.writer.flush() {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut buffer = [0; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut buffer = [0; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+fn read_i64<R: Read>(reader: &mut R) -> io::Result<i64> {
+    let mut buffer = [0; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(i64::from_le_bytes(buffer))
+}
+
+fn read_event<R: Read>(reader: &mut R, counter: u64) -> io::Result<TraceEvent> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+
+    let address = read_u64(reader)? as Address;
+    let word_count = read_u8(reader)?;
+    let mut words = Vec::with_capacity(word_count as usize);
+    for _ in 0..word_count {
+        words.push(read_i64(reader)? as Value);
+    }
+    let instruction = disassembler::decode_at(&words, 0)
+        .ok_or_else(|| invalid("trace contains an undecodable instruction"))?;
+
+    let value_count = read_u8(reader)?;
+    let mut values = Vec::with_capacity(value_count as usize);
+    for _ in 0..value_count {
+        values.push(read_i64(reader)? as Value);
+    }
+
+    let flags = read_u8(reader)?;
+    let write = if flags & WRITE_FLAG != 0 {
+        Some((read_u64(reader)? as Address, read_i64(reader)? as Value))
+    } else {
+        None
+    };
+    let relative_base = if flags & RELATIVE_BASE_FLAG != 0 {
+        Some(read_i64(reader)? as Value)
+    } else {
+        None
+    };
+
+    Ok(TraceEvent {
+        counter: counter as usize,
+        address,
+        instruction,
+        values,
+        write,
+        relative_base
+    })
+}
+
+// Reads back everything a BinarySink wrote.
+pub fn read_binary_trace<R: Read>(reader: &mut R) -> io::Result<Vec<TraceEvent>> {
+    let mut events = Vec::new();
+
+    loop {
+        // Running out of input is only fine between events.
+        let counter = match read_u64(reader) {
+            Ok(counter) => counter,
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(events),
+            Err(e) => return Err(e)
+        };
+        events.push(read_event(reader, counter)?);
+    }
+}
+
+// Keeps only the most recent events, for looking at what led up to a crash
+// without paying to keep the whole history of a long run.
+#[derive(Debug, Clone)]
+pub struct RingBufferSink<V = Value> {
+    capacity: usize,
+    events: VecDeque<TraceEvent<V>>,
+    // Doesn't count towards capacity, it's the whole point of a post-mortem.
+    fault: Option<Fault<V>>
+}
+
+impl<V: Number> RingBufferSink<V> {
+    pub fn new(capacity: usize) -> Self {
+        RingBufferSink {
+            capacity,
+            events: VecDeque::with_capacity(capacity),
+            fault: None
+        }
+    }
+
+    // Oldest first.
+    pub fn events(&self) -> impl Iterator<Item=&TraceEvent<V>> {
+        self.events.iter()
+    }
+
+    // What the run failed on, if it did, which comes after all the events.
+    pub fn fault(&self) -> Option<&Fault<V>> {
+        self.fault.as_ref()
+    }
+
+    pub fn dump(&self) -> String {
+        self.events.iter()
+            .map(|event| event.to_string())
+            .chain(self.fault.iter().map(|fault| fault.to_string()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl<V: Number> TraceSink<V> for RingBufferSink<V> {
+    fn record(&mut self, event: &TraceEvent<V>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+        // The machine was run again after failing, so that's old news.
+        self.fault = None;
+    }
+
+    fn fault(&mut self, fault: &Fault<V>) {
+        self.fault = Some(fault.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Machine, OperationalError, Opcode};
+    use std::io::BufWriter;
+
+    #[test]
+    fn text_trace() -> Result<(), OperationalError> {
+        let sink = Arc::new(Mutex::new(TextSink::new(Vec::new())));
+        let mut machine = Machine::from_slots(vec![109, 2, 21101, 3, 4, 5, 99, 0]);
+        machine.trace_to(sink.clone());
+        machine.run()?;
+
+        let text = String::from_utf8(sink.lock().unwrap().writer.clone()).unwrap();
+        assert_eq!(
+            vec![
+                "       0     0: RBO  #2                  ; 2 -> rb = 2",
+                "       1     2: ADD  #3, #4, rb+5        ; 3, 4 -> [7] = 7",
+                "       2     6: HLT"
+            ],
+            text.lines().collect::<Vec<&str>>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn binary_round_trip() -> Result<(), OperationalError> {
+        let binary = Arc::new(Mutex::new(BinarySink::new(Vec::new())));
+        let ring = Arc::new(Mutex::new(RingBufferSink::new(100)));
+
+        let mut machine = Machine::from_slots(vec![
+            109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99
+        ]);
+        let mut copy = machine.clone();
+        machine.trace_to(binary.clone());
+        copy.trace_to(ring.clone());
+        machine.run()?;
+        copy.run()?;
+
+        let bytes = binary.lock().unwrap().writer.clone();
+        let events = read_binary_trace(&mut bytes.as_slice()).unwrap();
+        let expected: Vec<TraceEvent> = ring.lock().unwrap().events().cloned().collect();
+        assert!(events.len() > 16);
+        assert_eq!(expected, events);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ring_buffer_post_mortem() {
+        let ring = Arc::new(Mutex::new(RingBufferSink::new(2)));
+        // Three adds and then an invalid opcode.
+        let mut machine = Machine::from_slots(vec![
+            1101, 1, 1, 13, 1101, 2, 2, 13, 1101, 3, 3, 13, 42, 0
+        ]);
+        machine.trace_to(ring.clone());
+
+        assert!(machine.run().is_err());
+
+        let ring = ring.lock().unwrap();
+        let events: Vec<&TraceEvent> = ring.events().collect();
+        assert_eq!(2, events.len());
+        assert_eq!(4, events[0].address);
+        assert_eq!(Opcode::Add, events[1].instruction.opcode);
+        assert_eq!(Some((13, 6)), events[1].write);
+
+        // 42 doesn't decode, so all we know is where it was.
+        let fault = ring.fault().unwrap();
+        assert_eq!(3, fault.counter);
+        assert_eq!(12, fault.address);
+        assert_eq!(None, fault.instruction);
+    }
+
+    #[test]
+    fn faulting_instruction() {
+        let ring = Arc::new(Mutex::new(RingBufferSink::new(10)));
+        // The second add writes to a negative address.
+        let mut machine = Machine::from_slots(vec![1101, 1, 1, 9, 1101, 2, 2, -1, 99, 0]);
+        machine.trace_to(ring.clone());
+
+        assert!(machine.run().is_err());
+
+        let ring = ring.lock().unwrap();
+        assert_eq!(1, ring.events().count());
+        let fault = ring.fault().unwrap();
+        assert_eq!(1, fault.counter);
+        assert_eq!(4, fault.address);
+        assert_eq!(Some(Opcode::Add), fault.instruction.as_ref().map(|i| i.opcode));
+        assert_eq!(ring.dump().lines().last().unwrap(), fault.to_string());
+        assert!(fault.to_string().contains("failed:"));
+    }
+
+    #[test]
+    fn flush_on_detach() -> Result<(), OperationalError> {
+        let sink = Arc::new(Mutex::new(TextSink::new(BufWriter::new(Vec::new()))));
+        let mut machine = Machine::from_slots(vec![1101, 1, 1, 5, 99, 0]);
+        machine.trace_to(sink.clone());
+        machine.run()?;
+        assert!(sink.lock().unwrap().writer.get_ref().is_empty());
+
+        machine.stop_tracing();
+        let text = String::from_utf8(sink.lock().unwrap().writer.get_ref().clone()).unwrap();
+        assert_eq!(2, text.lines().count());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,2131 @@
+// Generated from an Intcode program by `cargo run --bin transpile`. Don't
+// edit it by hand, transpile the program again instead.
+
+use crate::intcode::Value;
+use crate::intcode::transpiler::{Compiled, Suspend};
+
+pub const PROGRAM: [Value; 973] = [
+    1102, 34463338, 34463338, 63, 1007, 63, 34463338, 63, 1005, 63,
+    53, 1101, 0, 3, 1000, 109, 988, 209, 12, 9,
+    1000, 209, 6, 209, 3, 203, 0, 1008, 1000, 1,
+    63, 1005, 63, 65, 1008, 1000, 2, 63, 1005, 63,
+    904, 1008, 1000, 0, 63, 1005, 63, 58, 4, 25,
+    104, 0, 99, 4, 0, 104, 0, 99, 4, 17,
+    104, 0, 99, 0, 0, 1101, 0, 396, 1029, 1101,
+    0, 356, 1023, 1101, 401, 0, 1028, 1101, 24, 0,
+    1008, 1101, 33, 0, 1019, 1101, 35, 0, 1010, 1102,
+    359, 1, 1022, 1102, 32, 1, 1001, 1101, 37, 0,
+    1004, 1101, 0, 31, 1009, 1101, 0, 30, 1003, 1101,
+    28, 0, 1002, 1102, 1, 36, 1014, 1102, 20, 1,
+    1012, 1101, 21, 0, 1000, 1101, 0, 22, 1015, 1102,
+    23, 1, 1013, 1102, 1, 1, 1021, 1102, 1, 39,
+    1007, 1102, 26, 1, 1017, 1101, 0, 38, 1016, 1101,
+    0, 437, 1024, 1102, 432, 1, 1025, 1101, 0, 421,
+    1026, 1101, 0, 29, 1005, 1101, 27, 0, 1011, 1102,
+    1, 0, 1020, 1101, 0, 25, 1018, 1101, 0, 414,
+    1027, 1102, 34, 1, 1006, 109, 6, 2108, 33, -3,
+    63, 1005, 63, 201, 1001, 64, 1, 64, 1105, 1,
+    203, 4, 187, 1002, 64, 2, 64, 109, 14, 21108,
+    40, 40, -6, 1005, 1014, 221, 4, 209, 1105, 1,
+    225, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -21, 2102, 1, 3, 63, 1008, 63, 28, 63, 1005,
+    63, 251, 4, 231, 1001, 64, 1, 64, 1106, 0,
+    251, 1002, 64, 2, 64, 109, 12, 2101, 0, -3,
+    63, 1008, 63, 21, 63, 1005, 63, 275, 1001, 64,
+    1, 64, 1105, 1, 277, 4, 257, 1002, 64, 2,
+    64, 109, -10, 1207, 1, 27, 63, 1005, 63, 293,
+    1105, 1, 299, 4, 283, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, 9, 21108, 41, 42, 3, 1005,
+    1013, 315, 1105, 1, 321, 4, 305, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, -12, 1202, 6, 1,
+    63, 1008, 63, 37, 63, 1005, 63, 347, 4, 327,
+    1001, 64, 1, 64, 1105, 1, 347, 1002, 64, 2,
+    64, 109, 29, 2105, 1, -4, 1105, 1, 365, 4,
+    353, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -17, 2108, 32, -9, 63, 1005, 63, 387, 4, 371,
+    1001, 64, 1, 64, 1105, 1, 387, 1002, 64, 2,
+    64, 109, 17, 2106, 0, 1, 4, 393, 1105, 1,
+    405, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    1, 2106, 0, -1, 1001, 64, 1, 64, 1106, 0,
+    423, 4, 411, 1002, 64, 2, 64, 109, -13, 2105,
+    1, 9, 4, 429, 1106, 0, 441, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, 3, 21107, 42, 41,
+    -1, 1005, 1017, 461, 1001, 64, 1, 64, 1106, 0,
+    463, 4, 447, 1002, 64, 2, 64, 109, -4, 21107,
+    43, 44, 1, 1005, 1015, 481, 4, 469, 1106, 0,
+    485, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -6, 21101, 44, 0, 6, 1008, 1014, 47, 63, 1005,
+    63, 505, 1106, 0, 511, 4, 491, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, -6, 1208, -1, 32,
+    63, 1005, 63, 529, 4, 517, 1105, 1, 533, 1001,
+    64, 1, 64, 1002, 64, 2, 64, 109, 11, 1205,
+    7, 545, 1106, 0, 551, 4, 539, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, 11, 21102, 45, 1,
+    -7, 1008, 1017, 48, 63, 1005, 63, 575, 1001, 64,
+    1, 64, 1106, 0, 577, 4, 557, 1002, 64, 2,
+    64, 109, -8, 1206, 5, 593, 1001, 64, 1, 64,
+    1105, 1, 595, 4, 583, 1002, 64, 2, 64, 109,
+    7, 1206, -3, 609, 4, 601, 1106, 0, 613, 1001,
+    64, 1, 64, 1002, 64, 2, 64, 109, -10, 2101,
+    0, -6, 63, 1008, 63, 39, 63, 1005, 63, 635,
+    4, 619, 1106, 0, 639, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, -9, 1208, 0, 39, 63, 1005,
+    63, 655, 1106, 0, 661, 4, 645, 1001, 64, 1,
+    64, 1002, 64, 2, 64, 109, 4, 2107, 25, 0,
+    63, 1005, 63, 681, 1001, 64, 1, 64, 1105, 1,
+    683, 4, 667, 1002, 64, 2, 64, 109, -5, 2107,
+    31, -2, 63, 1005, 63, 701, 4, 689, 1106, 0,
+    705, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    19, 1205, -1, 719, 4, 711, 1105, 1, 723, 1001,
+    64, 1, 64, 1002, 64, 2, 64, 109, -17, 1201,
+    3, 0, 63, 1008, 63, 24, 63, 1005, 63, 745,
+    4, 729, 1106, 0, 749, 1001, 64, 1, 64, 1002,
+    64, 2, 64, 109, 13, 21102, 46, 1, -3, 1008,
+    1015, 46, 63, 1005, 63, 771, 4, 755, 1105, 1,
+    775, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -13, 1207, 4, 32, 63, 1005, 63, 793, 4, 781,
+    1106, 0, 797, 1001, 64, 1, 64, 1002, 64, 2,
+    64, 109, 7, 2102, 1, -9, 63, 1008, 63, 27,
+    63, 1005, 63, 821, 1001, 64, 1, 64, 1105, 1,
+    823, 4, 803, 1002, 64, 2, 64, 109, -18, 1201,
+    8, 0, 63, 1008, 63, 25, 63, 1005, 63, 847,
+    1001, 64, 1, 64, 1106, 0, 849, 4, 829, 1002,
+    64, 2, 64, 109, 23, 21101, 47, 0, 2, 1008,
+    1019, 47, 63, 1005, 63, 871, 4, 855, 1106, 0,
+    875, 1001, 64, 1, 64, 1002, 64, 2, 64, 109,
+    -22, 1202, 5, 1, 63, 1008, 63, 19, 63, 1005,
+    63, 899, 1001, 64, 1, 64, 1106, 0, 901, 4,
+    881, 4, 64, 99, 21102, 27, 1, 1, 21102, 1,
+    915, 0, 1105, 1, 922, 21201, 1, 25165, 1, 204,
+    1, 99, 109, 3, 1207, -2, 3, 63, 1005, 63,
+    964, 21201, -2, -1, 1, 21102, 942, 1, 0, 1105,
+    1, 922, 22102, 1, 1, -1, 21201, -2, -3, 1,
+    21101, 0, 957, 0, 1105, 1, 922, 22201, 1, -1,
+    -2, 1106, 0, 968, 21201, -2, 0, -2, 109, -3,
+    2105, 1, 0
+];
+
+static CODE: [bool; 973] = [
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, false, false, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true
+];
+
+pub fn machine() -> Compiled {
+    Compiled::new(&PROGRAM, &CODE, execute)
+}
+
+fn execute(m: &mut Compiled) -> Option<Suspend> {
+    loop {
+        match m.pointer() {
+            0 => {
+                // 0: MUL  #34463338, #34463338, [63]
+                m.at(0);
+                let a: Value = 34463338;
+                let b: Value = 34463338;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 4)?;
+                // 4: LT   [63], #34463338, [63]
+                m.at(4);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 34463338;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 8)?;
+                // 8: JT   [63], #53
+                m.at(8);
+                if m.read_at(63)? != 0 {
+                    m.at(53);
+                    continue;
+                }
+                m.at(11);
+                continue;
+            },
+            11 => {
+                // 11: ADD  #0, #3, [1000]
+                m.at(11);
+                let a: Value = 0;
+                let b: Value = 3;
+                let address = m.destination(1000)?;
+                m.store(address, a.checked_add(b)?, 15)?;
+                // 15: RBO  #988
+                m.at(15);
+                m.offset_base(988)?;
+                // 17: RBO  rb+12
+                m.at(17);
+                m.offset_base(m.read_at(m.relative(12)?)?)?;
+                // 19: RBO  [1000]
+                m.at(19);
+                m.offset_base(m.read_at(1000)?)?;
+                m.at(21);
+                continue;
+            },
+            21 => {
+                // 21: RBO  rb+6
+                m.at(21);
+                m.offset_base(m.read_at(m.relative(6)?)?)?;
+                m.at(23);
+                continue;
+            },
+            22 => {
+                // 22: JF   [209], [3]
+                m.at(22);
+                if m.read_at(209)? == 0 {
+                    m.at(m.address(m.read_at(3)?)?);
+                    continue;
+                }
+                m.at(25);
+                continue;
+            },
+            23 => {
+                // 23: RBO  rb+3
+                m.at(23);
+                m.offset_base(m.read_at(m.relative(3)?)?)?;
+                m.at(25);
+                continue;
+            },
+            24 => {
+                // 24: IN   [203]
+                m.at(24);
+                let address = m.destination(203)?;
+                let value = match m.input() {
+                    Some(value) => value,
+                    None => return Some(Suspend::Blocked)
+                };
+                m.store(address, value, 26)?;
+                m.at(26);
+                return None;
+            },
+            25 => {
+                // 25: IN   rb+0
+                m.at(25);
+                let address = m.destination(m.relative(0)?)?;
+                let value = match m.input() {
+                    Some(value) => value,
+                    None => return Some(Suspend::Blocked)
+                };
+                m.store(address, value, 27)?;
+                m.at(27);
+                continue;
+            },
+            27 => {
+                // 27: EQ   [1000], #1, [63]
+                m.at(27);
+                let a: Value = m.read_at(1000)?;
+                let b: Value = 1;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 31)?;
+                m.at(31);
+                continue;
+            },
+            29 => {
+                // 29: ADD  [63], [1005], [63]
+                m.at(29);
+                let a: Value = m.read_at(63)?;
+                let b: Value = m.read_at(1005)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_add(b)?, 33)?;
+                m.at(33);
+                return None;
+            },
+            31 => {
+                // 31: JT   [63], #65
+                m.at(31);
+                if m.read_at(63)? != 0 {
+                    m.at(65);
+                    continue;
+                }
+                m.at(34);
+                continue;
+            },
+            34 => {
+                // 34: EQ   [1000], #2, [63]
+                m.at(34);
+                let a: Value = m.read_at(1000)?;
+                let b: Value = 2;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 38)?;
+                m.at(38);
+                continue;
+            },
+            36 => {
+                // 36: MUL  [63], [1005], [63]
+                m.at(36);
+                let a: Value = m.read_at(63)?;
+                let b: Value = m.read_at(1005)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 40)?;
+                m.at(40);
+                return None;
+            },
+            38 => {
+                // 38: JT   [63], #904
+                m.at(38);
+                if m.read_at(63)? != 0 {
+                    m.at(904);
+                    continue;
+                }
+                m.at(41);
+                continue;
+            },
+            41 => {
+                // 41: EQ   [1000], #0, [63]
+                m.at(41);
+                let a: Value = m.read_at(1000)?;
+                let b: Value = 0;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 45)?;
+                m.at(45);
+                continue;
+            },
+            45 => {
+                // 45: JT   [63], #58
+                m.at(45);
+                if m.read_at(63)? != 0 {
+                    m.at(58);
+                    continue;
+                }
+                m.at(48);
+                continue;
+            },
+            48 => {
+                // 48: OUT  [25]
+                m.at(48);
+                m.output(m.read_at(25)?);
+                // 50: OUT  #0
+                m.at(50);
+                m.output(0);
+                // 52: HLT
+                m.at(52);
+                return Some(Suspend::Halted);
+            },
+            53 => {
+                // 53: OUT  [0]
+                m.at(53);
+                m.output(m.read_at(0)?);
+                // 55: OUT  #0
+                m.at(55);
+                m.output(0);
+                // 57: HLT
+                m.at(57);
+                return Some(Suspend::Halted);
+            },
+            58 => {
+                // 58: OUT  [17]
+                m.at(58);
+                m.output(m.read_at(17)?);
+                // 60: OUT  #0
+                m.at(60);
+                m.output(0);
+                // 62: HLT
+                m.at(62);
+                return Some(Suspend::Halted);
+            },
+            65 => {
+                // 65: ADD  #0, #396, [1029]
+                m.at(65);
+                let a: Value = 0;
+                let b: Value = 396;
+                let address = m.destination(1029)?;
+                m.store(address, a.checked_add(b)?, 69)?;
+                // 69: ADD  #0, #356, [1023]
+                m.at(69);
+                let a: Value = 0;
+                let b: Value = 356;
+                let address = m.destination(1023)?;
+                m.store(address, a.checked_add(b)?, 73)?;
+                // 73: ADD  #401, #0, [1028]
+                m.at(73);
+                let a: Value = 401;
+                let b: Value = 0;
+                let address = m.destination(1028)?;
+                m.store(address, a.checked_add(b)?, 77)?;
+                // 77: ADD  #24, #0, [1008]
+                m.at(77);
+                let a: Value = 24;
+                let b: Value = 0;
+                let address = m.destination(1008)?;
+                m.store(address, a.checked_add(b)?, 81)?;
+                // 81: ADD  #33, #0, [1019]
+                m.at(81);
+                let a: Value = 33;
+                let b: Value = 0;
+                let address = m.destination(1019)?;
+                m.store(address, a.checked_add(b)?, 85)?;
+                // 85: ADD  #35, #0, [1010]
+                m.at(85);
+                let a: Value = 35;
+                let b: Value = 0;
+                let address = m.destination(1010)?;
+                m.store(address, a.checked_add(b)?, 89)?;
+                // 89: MUL  #359, #1, [1022]
+                m.at(89);
+                let a: Value = 359;
+                let b: Value = 1;
+                let address = m.destination(1022)?;
+                m.store(address, a.checked_mul(b)?, 93)?;
+                // 93: MUL  #32, #1, [1001]
+                m.at(93);
+                let a: Value = 32;
+                let b: Value = 1;
+                let address = m.destination(1001)?;
+                m.store(address, a.checked_mul(b)?, 97)?;
+                // 97: ADD  #37, #0, [1004]
+                m.at(97);
+                let a: Value = 37;
+                let b: Value = 0;
+                let address = m.destination(1004)?;
+                m.store(address, a.checked_add(b)?, 101)?;
+                // 101: ADD  #0, #31, [1009]
+                m.at(101);
+                let a: Value = 0;
+                let b: Value = 31;
+                let address = m.destination(1009)?;
+                m.store(address, a.checked_add(b)?, 105)?;
+                // 105: ADD  #0, #30, [1003]
+                m.at(105);
+                let a: Value = 0;
+                let b: Value = 30;
+                let address = m.destination(1003)?;
+                m.store(address, a.checked_add(b)?, 109)?;
+                // 109: ADD  #28, #0, [1002]
+                m.at(109);
+                let a: Value = 28;
+                let b: Value = 0;
+                let address = m.destination(1002)?;
+                m.store(address, a.checked_add(b)?, 113)?;
+                // 113: MUL  #1, #36, [1014]
+                m.at(113);
+                let a: Value = 1;
+                let b: Value = 36;
+                let address = m.destination(1014)?;
+                m.store(address, a.checked_mul(b)?, 117)?;
+                // 117: MUL  #20, #1, [1012]
+                m.at(117);
+                let a: Value = 20;
+                let b: Value = 1;
+                let address = m.destination(1012)?;
+                m.store(address, a.checked_mul(b)?, 121)?;
+                // 121: ADD  #21, #0, [1000]
+                m.at(121);
+                let a: Value = 21;
+                let b: Value = 0;
+                let address = m.destination(1000)?;
+                m.store(address, a.checked_add(b)?, 125)?;
+                // 125: ADD  #0, #22, [1015]
+                m.at(125);
+                let a: Value = 0;
+                let b: Value = 22;
+                let address = m.destination(1015)?;
+                m.store(address, a.checked_add(b)?, 129)?;
+                // 129: MUL  #23, #1, [1013]
+                m.at(129);
+                let a: Value = 23;
+                let b: Value = 1;
+                let address = m.destination(1013)?;
+                m.store(address, a.checked_mul(b)?, 133)?;
+                // 133: MUL  #1, #1, [1021]
+                m.at(133);
+                let a: Value = 1;
+                let b: Value = 1;
+                let address = m.destination(1021)?;
+                m.store(address, a.checked_mul(b)?, 137)?;
+                // 137: MUL  #1, #39, [1007]
+                m.at(137);
+                let a: Value = 1;
+                let b: Value = 39;
+                let address = m.destination(1007)?;
+                m.store(address, a.checked_mul(b)?, 141)?;
+                // 141: MUL  #26, #1, [1017]
+                m.at(141);
+                let a: Value = 26;
+                let b: Value = 1;
+                let address = m.destination(1017)?;
+                m.store(address, a.checked_mul(b)?, 145)?;
+                // 145: ADD  #0, #38, [1016]
+                m.at(145);
+                let a: Value = 0;
+                let b: Value = 38;
+                let address = m.destination(1016)?;
+                m.store(address, a.checked_add(b)?, 149)?;
+                // 149: ADD  #0, #437, [1024]
+                m.at(149);
+                let a: Value = 0;
+                let b: Value = 437;
+                let address = m.destination(1024)?;
+                m.store(address, a.checked_add(b)?, 153)?;
+                // 153: MUL  #432, #1, [1025]
+                m.at(153);
+                let a: Value = 432;
+                let b: Value = 1;
+                let address = m.destination(1025)?;
+                m.store(address, a.checked_mul(b)?, 157)?;
+                // 157: ADD  #0, #421, [1026]
+                m.at(157);
+                let a: Value = 0;
+                let b: Value = 421;
+                let address = m.destination(1026)?;
+                m.store(address, a.checked_add(b)?, 161)?;
+                // 161: ADD  #0, #29, [1005]
+                m.at(161);
+                let a: Value = 0;
+                let b: Value = 29;
+                let address = m.destination(1005)?;
+                m.store(address, a.checked_add(b)?, 165)?;
+                // 165: ADD  #27, #0, [1011]
+                m.at(165);
+                let a: Value = 27;
+                let b: Value = 0;
+                let address = m.destination(1011)?;
+                m.store(address, a.checked_add(b)?, 169)?;
+                // 169: MUL  #1, #0, [1020]
+                m.at(169);
+                let a: Value = 1;
+                let b: Value = 0;
+                let address = m.destination(1020)?;
+                m.store(address, a.checked_mul(b)?, 173)?;
+                // 173: ADD  #0, #25, [1018]
+                m.at(173);
+                let a: Value = 0;
+                let b: Value = 25;
+                let address = m.destination(1018)?;
+                m.store(address, a.checked_add(b)?, 177)?;
+                // 177: ADD  #0, #414, [1027]
+                m.at(177);
+                let a: Value = 0;
+                let b: Value = 414;
+                let address = m.destination(1027)?;
+                m.store(address, a.checked_add(b)?, 181)?;
+                // 181: MUL  #34, #1, [1006]
+                m.at(181);
+                let a: Value = 34;
+                let b: Value = 1;
+                let address = m.destination(1006)?;
+                m.store(address, a.checked_mul(b)?, 185)?;
+                // 185: RBO  #6
+                m.at(185);
+                m.offset_base(6)?;
+                // 187: EQ   #33, rb-3, [63]
+                m.at(187);
+                let a: Value = 33;
+                let b: Value = m.read_at(m.relative(-3)?)?;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 191)?;
+                // 191: JT   [63], #201
+                m.at(191);
+                if m.read_at(63)? != 0 {
+                    m.at(201);
+                    continue;
+                }
+                m.at(194);
+                continue;
+            },
+            194 => {
+                // 194: ADD  [64], #1, [64]
+                m.at(194);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 198)?;
+                // 198: JT   #1, #203
+                m.at(198);
+                m.at(203);
+                continue;
+            },
+            201 => {
+                // 201: OUT  [187]
+                m.at(201);
+                m.output(m.read_at(187)?);
+                m.at(203);
+                continue;
+            },
+            203 => {
+                // 203: MUL  [64], #2, [64]
+                m.at(203);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 207)?;
+                // 207: RBO  #14
+                m.at(207);
+                m.offset_base(14)?;
+                // 209: EQ   #40, #40, rb-6
+                m.at(209);
+                let a: Value = 40;
+                let b: Value = 40;
+                let address = m.destination(m.relative(-6)?)?;
+                m.store(address, (a == b) as Value, 213)?;
+                // 213: JT   [1014], #221
+                m.at(213);
+                if m.read_at(1014)? != 0 {
+                    m.at(221);
+                    continue;
+                }
+                m.at(216);
+                continue;
+            },
+            216 => {
+                // 216: OUT  [209]
+                m.at(216);
+                m.output(m.read_at(209)?);
+                // 218: JT   #1, #225
+                m.at(218);
+                m.at(225);
+                continue;
+            },
+            221 => {
+                // 221: ADD  [64], #1, [64]
+                m.at(221);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 225)?;
+                m.at(225);
+                continue;
+            },
+            225 => {
+                // 225: MUL  [64], #2, [64]
+                m.at(225);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 229)?;
+                // 229: RBO  #-21
+                m.at(229);
+                m.offset_base(-21)?;
+                // 231: MUL  #1, rb+3, [63]
+                m.at(231);
+                let a: Value = 1;
+                let b: Value = m.read_at(m.relative(3)?)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 235)?;
+                // 235: EQ   [63], #28, [63]
+                m.at(235);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 28;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 239)?;
+                // 239: JT   [63], #251
+                m.at(239);
+                if m.read_at(63)? != 0 {
+                    m.at(251);
+                    continue;
+                }
+                m.at(242);
+                continue;
+            },
+            242 => {
+                // 242: OUT  [231]
+                m.at(242);
+                m.output(m.read_at(231)?);
+                // 244: ADD  [64], #1, [64]
+                m.at(244);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 248)?;
+                // 248: JF   #0, #251
+                m.at(248);
+                m.at(251);
+                continue;
+            },
+            251 => {
+                // 251: MUL  [64], #2, [64]
+                m.at(251);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 255)?;
+                // 255: RBO  #12
+                m.at(255);
+                m.offset_base(12)?;
+                // 257: ADD  #0, rb-3, [63]
+                m.at(257);
+                let a: Value = 0;
+                let b: Value = m.read_at(m.relative(-3)?)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_add(b)?, 261)?;
+                // 261: EQ   [63], #21, [63]
+                m.at(261);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 21;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 265)?;
+                // 265: JT   [63], #275
+                m.at(265);
+                if m.read_at(63)? != 0 {
+                    m.at(275);
+                    continue;
+                }
+                m.at(268);
+                continue;
+            },
+            268 => {
+                // 268: ADD  [64], #1, [64]
+                m.at(268);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 272)?;
+                // 272: JT   #1, #277
+                m.at(272);
+                m.at(277);
+                continue;
+            },
+            275 => {
+                // 275: OUT  [257]
+                m.at(275);
+                m.output(m.read_at(257)?);
+                m.at(277);
+                continue;
+            },
+            277 => {
+                // 277: MUL  [64], #2, [64]
+                m.at(277);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 281)?;
+                // 281: RBO  #-10
+                m.at(281);
+                m.offset_base(-10)?;
+                // 283: LT   rb+1, #27, [63]
+                m.at(283);
+                let a: Value = m.read_at(m.relative(1)?)?;
+                let b: Value = 27;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 287)?;
+                // 287: JT   [63], #293
+                m.at(287);
+                if m.read_at(63)? != 0 {
+                    m.at(293);
+                    continue;
+                }
+                m.at(290);
+                continue;
+            },
+            290 => {
+                // 290: JT   #1, #299
+                m.at(290);
+                m.at(299);
+                continue;
+            },
+            293 => {
+                // 293: OUT  [283]
+                m.at(293);
+                m.output(m.read_at(283)?);
+                // 295: ADD  [64], #1, [64]
+                m.at(295);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 299)?;
+                m.at(299);
+                continue;
+            },
+            299 => {
+                // 299: MUL  [64], #2, [64]
+                m.at(299);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 303)?;
+                // 303: RBO  #9
+                m.at(303);
+                m.offset_base(9)?;
+                // 305: EQ   #41, #42, rb+3
+                m.at(305);
+                let a: Value = 41;
+                let b: Value = 42;
+                let address = m.destination(m.relative(3)?)?;
+                m.store(address, (a == b) as Value, 309)?;
+                // 309: JT   [1013], #315
+                m.at(309);
+                if m.read_at(1013)? != 0 {
+                    m.at(315);
+                    continue;
+                }
+                m.at(312);
+                continue;
+            },
+            312 => {
+                // 312: JT   #1, #321
+                m.at(312);
+                m.at(321);
+                continue;
+            },
+            315 => {
+                // 315: OUT  [305]
+                m.at(315);
+                m.output(m.read_at(305)?);
+                // 317: ADD  [64], #1, [64]
+                m.at(317);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 321)?;
+                m.at(321);
+                continue;
+            },
+            321 => {
+                // 321: MUL  [64], #2, [64]
+                m.at(321);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 325)?;
+                // 325: RBO  #-12
+                m.at(325);
+                m.offset_base(-12)?;
+                // 327: MUL  rb+6, #1, [63]
+                m.at(327);
+                let a: Value = m.read_at(m.relative(6)?)?;
+                let b: Value = 1;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 331)?;
+                // 331: EQ   [63], #37, [63]
+                m.at(331);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 37;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 335)?;
+                // 335: JT   [63], #347
+                m.at(335);
+                if m.read_at(63)? != 0 {
+                    m.at(347);
+                    continue;
+                }
+                m.at(338);
+                continue;
+            },
+            338 => {
+                // 338: OUT  [327]
+                m.at(338);
+                m.output(m.read_at(327)?);
+                // 340: ADD  [64], #1, [64]
+                m.at(340);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 344)?;
+                // 344: JT   #1, #347
+                m.at(344);
+                m.at(347);
+                continue;
+            },
+            347 => {
+                // 347: MUL  [64], #2, [64]
+                m.at(347);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 351)?;
+                // 351: RBO  #29
+                m.at(351);
+                m.offset_base(29)?;
+                // 353: JT   #1, rb-4
+                m.at(353);
+                m.at(m.address(m.read_at(m.relative(-4)?)?)?);
+                continue;
+            },
+            356 => {
+                // 356: JT   #1, #365
+                m.at(356);
+                m.at(365);
+                continue;
+            },
+            359 => {
+                // 359: OUT  [353]
+                m.at(359);
+                m.output(m.read_at(353)?);
+                // 361: ADD  [64], #1, [64]
+                m.at(361);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 365)?;
+                m.at(365);
+                continue;
+            },
+            365 => {
+                // 365: MUL  [64], #2, [64]
+                m.at(365);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 369)?;
+                // 369: RBO  #-17
+                m.at(369);
+                m.offset_base(-17)?;
+                // 371: EQ   #32, rb-9, [63]
+                m.at(371);
+                let a: Value = 32;
+                let b: Value = m.read_at(m.relative(-9)?)?;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 375)?;
+                // 375: JT   [63], #387
+                m.at(375);
+                if m.read_at(63)? != 0 {
+                    m.at(387);
+                    continue;
+                }
+                m.at(378);
+                continue;
+            },
+            378 => {
+                // 378: OUT  [371]
+                m.at(378);
+                m.output(m.read_at(371)?);
+                // 380: ADD  [64], #1, [64]
+                m.at(380);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 384)?;
+                // 384: JT   #1, #387
+                m.at(384);
+                m.at(387);
+                continue;
+            },
+            387 => {
+                // 387: MUL  [64], #2, [64]
+                m.at(387);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 391)?;
+                // 391: RBO  #17
+                m.at(391);
+                m.offset_base(17)?;
+                // 393: JF   #0, rb+1
+                m.at(393);
+                m.at(m.address(m.read_at(m.relative(1)?)?)?);
+                continue;
+            },
+            396 => {
+                // 396: OUT  [393]
+                m.at(396);
+                m.output(m.read_at(393)?);
+                // 398: JT   #1, #405
+                m.at(398);
+                m.at(405);
+                continue;
+            },
+            401 => {
+                // 401: ADD  [64], #1, [64]
+                m.at(401);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 405)?;
+                m.at(405);
+                continue;
+            },
+            405 => {
+                // 405: MUL  [64], #2, [64]
+                m.at(405);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 409)?;
+                // 409: RBO  #1
+                m.at(409);
+                m.offset_base(1)?;
+                // 411: JF   #0, rb-1
+                m.at(411);
+                m.at(m.address(m.read_at(m.relative(-1)?)?)?);
+                continue;
+            },
+            414 => {
+                // 414: ADD  [64], #1, [64]
+                m.at(414);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 418)?;
+                // 418: JF   #0, #423
+                m.at(418);
+                m.at(423);
+                continue;
+            },
+            421 => {
+                // 421: OUT  [411]
+                m.at(421);
+                m.output(m.read_at(411)?);
+                m.at(423);
+                continue;
+            },
+            423 => {
+                // 423: MUL  [64], #2, [64]
+                m.at(423);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 427)?;
+                // 427: RBO  #-13
+                m.at(427);
+                m.offset_base(-13)?;
+                // 429: JT   #1, rb+9
+                m.at(429);
+                m.at(m.address(m.read_at(m.relative(9)?)?)?);
+                continue;
+            },
+            432 => {
+                // 432: OUT  [429]
+                m.at(432);
+                m.output(m.read_at(429)?);
+                // 434: JF   #0, #441
+                m.at(434);
+                m.at(441);
+                continue;
+            },
+            437 => {
+                // 437: ADD  [64], #1, [64]
+                m.at(437);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 441)?;
+                m.at(441);
+                continue;
+            },
+            441 => {
+                // 441: MUL  [64], #2, [64]
+                m.at(441);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 445)?;
+                // 445: RBO  #3
+                m.at(445);
+                m.offset_base(3)?;
+                // 447: LT   #42, #41, rb-1
+                m.at(447);
+                let a: Value = 42;
+                let b: Value = 41;
+                let address = m.destination(m.relative(-1)?)?;
+                m.store(address, (a < b) as Value, 451)?;
+                // 451: JT   [1017], #461
+                m.at(451);
+                if m.read_at(1017)? != 0 {
+                    m.at(461);
+                    continue;
+                }
+                m.at(454);
+                continue;
+            },
+            454 => {
+                // 454: ADD  [64], #1, [64]
+                m.at(454);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 458)?;
+                // 458: JF   #0, #463
+                m.at(458);
+                m.at(463);
+                continue;
+            },
+            461 => {
+                // 461: OUT  [447]
+                m.at(461);
+                m.output(m.read_at(447)?);
+                m.at(463);
+                continue;
+            },
+            463 => {
+                // 463: MUL  [64], #2, [64]
+                m.at(463);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 467)?;
+                // 467: RBO  #-4
+                m.at(467);
+                m.offset_base(-4)?;
+                // 469: LT   #43, #44, rb+1
+                m.at(469);
+                let a: Value = 43;
+                let b: Value = 44;
+                let address = m.destination(m.relative(1)?)?;
+                m.store(address, (a < b) as Value, 473)?;
+                // 473: JT   [1015], #481
+                m.at(473);
+                if m.read_at(1015)? != 0 {
+                    m.at(481);
+                    continue;
+                }
+                m.at(476);
+                continue;
+            },
+            476 => {
+                // 476: OUT  [469]
+                m.at(476);
+                m.output(m.read_at(469)?);
+                // 478: JF   #0, #485
+                m.at(478);
+                m.at(485);
+                continue;
+            },
+            481 => {
+                // 481: ADD  [64], #1, [64]
+                m.at(481);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 485)?;
+                m.at(485);
+                continue;
+            },
+            485 => {
+                // 485: MUL  [64], #2, [64]
+                m.at(485);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 489)?;
+                // 489: RBO  #-6
+                m.at(489);
+                m.offset_base(-6)?;
+                // 491: ADD  #44, #0, rb+6
+                m.at(491);
+                let a: Value = 44;
+                let b: Value = 0;
+                let address = m.destination(m.relative(6)?)?;
+                m.store(address, a.checked_add(b)?, 495)?;
+                // 495: EQ   [1014], #47, [63]
+                m.at(495);
+                let a: Value = m.read_at(1014)?;
+                let b: Value = 47;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 499)?;
+                // 499: JT   [63], #505
+                m.at(499);
+                if m.read_at(63)? != 0 {
+                    m.at(505);
+                    continue;
+                }
+                m.at(502);
+                continue;
+            },
+            502 => {
+                // 502: JF   #0, #511
+                m.at(502);
+                m.at(511);
+                continue;
+            },
+            505 => {
+                // 505: OUT  [491]
+                m.at(505);
+                m.output(m.read_at(491)?);
+                // 507: ADD  [64], #1, [64]
+                m.at(507);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 511)?;
+                m.at(511);
+                continue;
+            },
+            511 => {
+                // 511: MUL  [64], #2, [64]
+                m.at(511);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 515)?;
+                // 515: RBO  #-6
+                m.at(515);
+                m.offset_base(-6)?;
+                // 517: EQ   rb-1, #32, [63]
+                m.at(517);
+                let a: Value = m.read_at(m.relative(-1)?)?;
+                let b: Value = 32;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 521)?;
+                // 521: JT   [63], #529
+                m.at(521);
+                if m.read_at(63)? != 0 {
+                    m.at(529);
+                    continue;
+                }
+                m.at(524);
+                continue;
+            },
+            524 => {
+                // 524: OUT  [517]
+                m.at(524);
+                m.output(m.read_at(517)?);
+                // 526: JT   #1, #533
+                m.at(526);
+                m.at(533);
+                continue;
+            },
+            529 => {
+                // 529: ADD  [64], #1, [64]
+                m.at(529);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 533)?;
+                m.at(533);
+                continue;
+            },
+            533 => {
+                // 533: MUL  [64], #2, [64]
+                m.at(533);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 537)?;
+                // 537: RBO  #11
+                m.at(537);
+                m.offset_base(11)?;
+                // 539: JT   rb+7, #545
+                m.at(539);
+                if m.read_at(m.relative(7)?)? != 0 {
+                    m.at(545);
+                    continue;
+                }
+                m.at(542);
+                continue;
+            },
+            542 => {
+                // 542: JF   #0, #551
+                m.at(542);
+                m.at(551);
+                continue;
+            },
+            545 => {
+                // 545: OUT  [539]
+                m.at(545);
+                m.output(m.read_at(539)?);
+                // 547: ADD  [64], #1, [64]
+                m.at(547);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 551)?;
+                m.at(551);
+                continue;
+            },
+            551 => {
+                // 551: MUL  [64], #2, [64]
+                m.at(551);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 555)?;
+                // 555: RBO  #11
+                m.at(555);
+                m.offset_base(11)?;
+                // 557: MUL  #45, #1, rb-7
+                m.at(557);
+                let a: Value = 45;
+                let b: Value = 1;
+                let address = m.destination(m.relative(-7)?)?;
+                m.store(address, a.checked_mul(b)?, 561)?;
+                // 561: EQ   [1017], #48, [63]
+                m.at(561);
+                let a: Value = m.read_at(1017)?;
+                let b: Value = 48;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 565)?;
+                // 565: JT   [63], #575
+                m.at(565);
+                if m.read_at(63)? != 0 {
+                    m.at(575);
+                    continue;
+                }
+                m.at(568);
+                continue;
+            },
+            568 => {
+                // 568: ADD  [64], #1, [64]
+                m.at(568);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 572)?;
+                // 572: JF   #0, #577
+                m.at(572);
+                m.at(577);
+                continue;
+            },
+            575 => {
+                // 575: OUT  [557]
+                m.at(575);
+                m.output(m.read_at(557)?);
+                m.at(577);
+                continue;
+            },
+            577 => {
+                // 577: MUL  [64], #2, [64]
+                m.at(577);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 581)?;
+                // 581: RBO  #-8
+                m.at(581);
+                m.offset_base(-8)?;
+                // 583: JF   rb+5, #593
+                m.at(583);
+                if m.read_at(m.relative(5)?)? == 0 {
+                    m.at(593);
+                    continue;
+                }
+                m.at(586);
+                continue;
+            },
+            586 => {
+                // 586: ADD  [64], #1, [64]
+                m.at(586);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 590)?;
+                // 590: JT   #1, #595
+                m.at(590);
+                m.at(595);
+                continue;
+            },
+            593 => {
+                // 593: OUT  [583]
+                m.at(593);
+                m.output(m.read_at(583)?);
+                m.at(595);
+                continue;
+            },
+            595 => {
+                // 595: MUL  [64], #2, [64]
+                m.at(595);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 599)?;
+                // 599: RBO  #7
+                m.at(599);
+                m.offset_base(7)?;
+                // 601: JF   rb-3, #609
+                m.at(601);
+                if m.read_at(m.relative(-3)?)? == 0 {
+                    m.at(609);
+                    continue;
+                }
+                m.at(604);
+                continue;
+            },
+            604 => {
+                // 604: OUT  [601]
+                m.at(604);
+                m.output(m.read_at(601)?);
+                // 606: JF   #0, #613
+                m.at(606);
+                m.at(613);
+                continue;
+            },
+            609 => {
+                // 609: ADD  [64], #1, [64]
+                m.at(609);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 613)?;
+                m.at(613);
+                continue;
+            },
+            613 => {
+                // 613: MUL  [64], #2, [64]
+                m.at(613);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 617)?;
+                // 617: RBO  #-10
+                m.at(617);
+                m.offset_base(-10)?;
+                // 619: ADD  #0, rb-6, [63]
+                m.at(619);
+                let a: Value = 0;
+                let b: Value = m.read_at(m.relative(-6)?)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_add(b)?, 623)?;
+                // 623: EQ   [63], #39, [63]
+                m.at(623);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 39;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 627)?;
+                // 627: JT   [63], #635
+                m.at(627);
+                if m.read_at(63)? != 0 {
+                    m.at(635);
+                    continue;
+                }
+                m.at(630);
+                continue;
+            },
+            630 => {
+                // 630: OUT  [619]
+                m.at(630);
+                m.output(m.read_at(619)?);
+                // 632: JF   #0, #639
+                m.at(632);
+                m.at(639);
+                continue;
+            },
+            635 => {
+                // 635: ADD  [64], #1, [64]
+                m.at(635);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 639)?;
+                m.at(639);
+                continue;
+            },
+            639 => {
+                // 639: MUL  [64], #2, [64]
+                m.at(639);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 643)?;
+                // 643: RBO  #-9
+                m.at(643);
+                m.offset_base(-9)?;
+                // 645: EQ   rb+0, #39, [63]
+                m.at(645);
+                let a: Value = m.read_at(m.relative(0)?)?;
+                let b: Value = 39;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 649)?;
+                // 649: JT   [63], #655
+                m.at(649);
+                if m.read_at(63)? != 0 {
+                    m.at(655);
+                    continue;
+                }
+                m.at(652);
+                continue;
+            },
+            652 => {
+                // 652: JF   #0, #661
+                m.at(652);
+                m.at(661);
+                continue;
+            },
+            655 => {
+                // 655: OUT  [645]
+                m.at(655);
+                m.output(m.read_at(645)?);
+                // 657: ADD  [64], #1, [64]
+                m.at(657);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 661)?;
+                m.at(661);
+                continue;
+            },
+            661 => {
+                // 661: MUL  [64], #2, [64]
+                m.at(661);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 665)?;
+                // 665: RBO  #4
+                m.at(665);
+                m.offset_base(4)?;
+                // 667: LT   #25, rb+0, [63]
+                m.at(667);
+                let a: Value = 25;
+                let b: Value = m.read_at(m.relative(0)?)?;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 671)?;
+                // 671: JT   [63], #681
+                m.at(671);
+                if m.read_at(63)? != 0 {
+                    m.at(681);
+                    continue;
+                }
+                m.at(674);
+                continue;
+            },
+            674 => {
+                // 674: ADD  [64], #1, [64]
+                m.at(674);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 678)?;
+                // 678: JT   #1, #683
+                m.at(678);
+                m.at(683);
+                continue;
+            },
+            681 => {
+                // 681: OUT  [667]
+                m.at(681);
+                m.output(m.read_at(667)?);
+                m.at(683);
+                continue;
+            },
+            683 => {
+                // 683: MUL  [64], #2, [64]
+                m.at(683);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 687)?;
+                // 687: RBO  #-5
+                m.at(687);
+                m.offset_base(-5)?;
+                // 689: LT   #31, rb-2, [63]
+                m.at(689);
+                let a: Value = 31;
+                let b: Value = m.read_at(m.relative(-2)?)?;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 693)?;
+                // 693: JT   [63], #701
+                m.at(693);
+                if m.read_at(63)? != 0 {
+                    m.at(701);
+                    continue;
+                }
+                m.at(696);
+                continue;
+            },
+            696 => {
+                // 696: OUT  [689]
+                m.at(696);
+                m.output(m.read_at(689)?);
+                // 698: JF   #0, #705
+                m.at(698);
+                m.at(705);
+                continue;
+            },
+            701 => {
+                // 701: ADD  [64], #1, [64]
+                m.at(701);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 705)?;
+                m.at(705);
+                continue;
+            },
+            705 => {
+                // 705: MUL  [64], #2, [64]
+                m.at(705);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 709)?;
+                // 709: RBO  #19
+                m.at(709);
+                m.offset_base(19)?;
+                // 711: JT   rb-1, #719
+                m.at(711);
+                if m.read_at(m.relative(-1)?)? != 0 {
+                    m.at(719);
+                    continue;
+                }
+                m.at(714);
+                continue;
+            },
+            714 => {
+                // 714: OUT  [711]
+                m.at(714);
+                m.output(m.read_at(711)?);
+                // 716: JT   #1, #723
+                m.at(716);
+                m.at(723);
+                continue;
+            },
+            719 => {
+                // 719: ADD  [64], #1, [64]
+                m.at(719);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 723)?;
+                m.at(723);
+                continue;
+            },
+            723 => {
+                // 723: MUL  [64], #2, [64]
+                m.at(723);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 727)?;
+                // 727: RBO  #-17
+                m.at(727);
+                m.offset_base(-17)?;
+                // 729: ADD  rb+3, #0, [63]
+                m.at(729);
+                let a: Value = m.read_at(m.relative(3)?)?;
+                let b: Value = 0;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_add(b)?, 733)?;
+                // 733: EQ   [63], #24, [63]
+                m.at(733);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 24;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 737)?;
+                // 737: JT   [63], #745
+                m.at(737);
+                if m.read_at(63)? != 0 {
+                    m.at(745);
+                    continue;
+                }
+                m.at(740);
+                continue;
+            },
+            740 => {
+                // 740: OUT  [729]
+                m.at(740);
+                m.output(m.read_at(729)?);
+                // 742: JF   #0, #749
+                m.at(742);
+                m.at(749);
+                continue;
+            },
+            745 => {
+                // 745: ADD  [64], #1, [64]
+                m.at(745);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 749)?;
+                m.at(749);
+                continue;
+            },
+            749 => {
+                // 749: MUL  [64], #2, [64]
+                m.at(749);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 753)?;
+                // 753: RBO  #13
+                m.at(753);
+                m.offset_base(13)?;
+                // 755: MUL  #46, #1, rb-3
+                m.at(755);
+                let a: Value = 46;
+                let b: Value = 1;
+                let address = m.destination(m.relative(-3)?)?;
+                m.store(address, a.checked_mul(b)?, 759)?;
+                // 759: EQ   [1015], #46, [63]
+                m.at(759);
+                let a: Value = m.read_at(1015)?;
+                let b: Value = 46;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 763)?;
+                // 763: JT   [63], #771
+                m.at(763);
+                if m.read_at(63)? != 0 {
+                    m.at(771);
+                    continue;
+                }
+                m.at(766);
+                continue;
+            },
+            766 => {
+                // 766: OUT  [755]
+                m.at(766);
+                m.output(m.read_at(755)?);
+                // 768: JT   #1, #775
+                m.at(768);
+                m.at(775);
+                continue;
+            },
+            771 => {
+                // 771: ADD  [64], #1, [64]
+                m.at(771);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 775)?;
+                m.at(775);
+                continue;
+            },
+            775 => {
+                // 775: MUL  [64], #2, [64]
+                m.at(775);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 779)?;
+                // 779: RBO  #-13
+                m.at(779);
+                m.offset_base(-13)?;
+                // 781: LT   rb+4, #32, [63]
+                m.at(781);
+                let a: Value = m.read_at(m.relative(4)?)?;
+                let b: Value = 32;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 785)?;
+                // 785: JT   [63], #793
+                m.at(785);
+                if m.read_at(63)? != 0 {
+                    m.at(793);
+                    continue;
+                }
+                m.at(788);
+                continue;
+            },
+            788 => {
+                // 788: OUT  [781]
+                m.at(788);
+                m.output(m.read_at(781)?);
+                // 790: JF   #0, #797
+                m.at(790);
+                m.at(797);
+                continue;
+            },
+            793 => {
+                // 793: ADD  [64], #1, [64]
+                m.at(793);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 797)?;
+                m.at(797);
+                continue;
+            },
+            797 => {
+                // 797: MUL  [64], #2, [64]
+                m.at(797);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 801)?;
+                // 801: RBO  #7
+                m.at(801);
+                m.offset_base(7)?;
+                // 803: MUL  #1, rb-9, [63]
+                m.at(803);
+                let a: Value = 1;
+                let b: Value = m.read_at(m.relative(-9)?)?;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 807)?;
+                // 807: EQ   [63], #27, [63]
+                m.at(807);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 27;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 811)?;
+                // 811: JT   [63], #821
+                m.at(811);
+                if m.read_at(63)? != 0 {
+                    m.at(821);
+                    continue;
+                }
+                m.at(814);
+                continue;
+            },
+            814 => {
+                // 814: ADD  [64], #1, [64]
+                m.at(814);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 818)?;
+                // 818: JT   #1, #823
+                m.at(818);
+                m.at(823);
+                continue;
+            },
+            821 => {
+                // 821: OUT  [803]
+                m.at(821);
+                m.output(m.read_at(803)?);
+                m.at(823);
+                continue;
+            },
+            823 => {
+                // 823: MUL  [64], #2, [64]
+                m.at(823);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 827)?;
+                // 827: RBO  #-18
+                m.at(827);
+                m.offset_base(-18)?;
+                // 829: ADD  rb+8, #0, [63]
+                m.at(829);
+                let a: Value = m.read_at(m.relative(8)?)?;
+                let b: Value = 0;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_add(b)?, 833)?;
+                // 833: EQ   [63], #25, [63]
+                m.at(833);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 25;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 837)?;
+                // 837: JT   [63], #847
+                m.at(837);
+                if m.read_at(63)? != 0 {
+                    m.at(847);
+                    continue;
+                }
+                m.at(840);
+                continue;
+            },
+            840 => {
+                // 840: ADD  [64], #1, [64]
+                m.at(840);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 844)?;
+                // 844: JF   #0, #849
+                m.at(844);
+                m.at(849);
+                continue;
+            },
+            847 => {
+                // 847: OUT  [829]
+                m.at(847);
+                m.output(m.read_at(829)?);
+                m.at(849);
+                continue;
+            },
+            849 => {
+                // 849: MUL  [64], #2, [64]
+                m.at(849);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 853)?;
+                // 853: RBO  #23
+                m.at(853);
+                m.offset_base(23)?;
+                // 855: ADD  #47, #0, rb+2
+                m.at(855);
+                let a: Value = 47;
+                let b: Value = 0;
+                let address = m.destination(m.relative(2)?)?;
+                m.store(address, a.checked_add(b)?, 859)?;
+                // 859: EQ   [1019], #47, [63]
+                m.at(859);
+                let a: Value = m.read_at(1019)?;
+                let b: Value = 47;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 863)?;
+                // 863: JT   [63], #871
+                m.at(863);
+                if m.read_at(63)? != 0 {
+                    m.at(871);
+                    continue;
+                }
+                m.at(866);
+                continue;
+            },
+            866 => {
+                // 866: OUT  [855]
+                m.at(866);
+                m.output(m.read_at(855)?);
+                // 868: JF   #0, #875
+                m.at(868);
+                m.at(875);
+                continue;
+            },
+            871 => {
+                // 871: ADD  [64], #1, [64]
+                m.at(871);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 875)?;
+                m.at(875);
+                continue;
+            },
+            875 => {
+                // 875: MUL  [64], #2, [64]
+                m.at(875);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 2;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_mul(b)?, 879)?;
+                // 879: RBO  #-22
+                m.at(879);
+                m.offset_base(-22)?;
+                // 881: MUL  rb+5, #1, [63]
+                m.at(881);
+                let a: Value = m.read_at(m.relative(5)?)?;
+                let b: Value = 1;
+                let address = m.destination(63)?;
+                m.store(address, a.checked_mul(b)?, 885)?;
+                // 885: EQ   [63], #19, [63]
+                m.at(885);
+                let a: Value = m.read_at(63)?;
+                let b: Value = 19;
+                let address = m.destination(63)?;
+                m.store(address, (a == b) as Value, 889)?;
+                // 889: JT   [63], #899
+                m.at(889);
+                if m.read_at(63)? != 0 {
+                    m.at(899);
+                    continue;
+                }
+                m.at(892);
+                continue;
+            },
+            892 => {
+                // 892: ADD  [64], #1, [64]
+                m.at(892);
+                let a: Value = m.read_at(64)?;
+                let b: Value = 1;
+                let address = m.destination(64)?;
+                m.store(address, a.checked_add(b)?, 896)?;
+                // 896: JF   #0, #901
+                m.at(896);
+                m.at(901);
+                continue;
+            },
+            899 => {
+                // 899: OUT  [881]
+                m.at(899);
+                m.output(m.read_at(881)?);
+                m.at(901);
+                continue;
+            },
+            901 => {
+                // 901: OUT  [64]
+                m.at(901);
+                m.output(m.read_at(64)?);
+                // 903: HLT
+                m.at(903);
+                return Some(Suspend::Halted);
+            },
+            904 => {
+                // 904: MUL  #27, #1, rb+1
+                m.at(904);
+                let a: Value = 27;
+                let b: Value = 1;
+                let address = m.destination(m.relative(1)?)?;
+                m.store(address, a.checked_mul(b)?, 908)?;
+                // 908: MUL  #1, #915, rb+0
+                m.at(908);
+                let a: Value = 1;
+                let b: Value = 915;
+                let address = m.destination(m.relative(0)?)?;
+                m.store(address, a.checked_mul(b)?, 912)?;
+                // 912: JT   #1, #922
+                m.at(912);
+                m.at(922);
+                continue;
+            },
+            915 => {
+                // 915: ADD  rb+1, #25165, rb+1
+                m.at(915);
+                let a: Value = m.read_at(m.relative(1)?)?;
+                let b: Value = 25165;
+                let address = m.destination(m.relative(1)?)?;
+                m.store(address, a.checked_add(b)?, 919)?;
+                // 919: OUT  rb+1
+                m.at(919);
+                m.output(m.read_at(m.relative(1)?)?);
+                // 921: HLT
+                m.at(921);
+                return Some(Suspend::Halted);
+            },
+            922 => {
+                // 922: RBO  #3
+                m.at(922);
+                m.offset_base(3)?;
+                // 924: LT   rb-2, #3, [63]
+                m.at(924);
+                let a: Value = m.read_at(m.relative(-2)?)?;
+                let b: Value = 3;
+                let address = m.destination(63)?;
+                m.store(address, (a < b) as Value, 928)?;
+                // 928: JT   [63], #964
+                m.at(928);
+                if m.read_at(63)? != 0 {
+                    m.at(964);
+                    continue;
+                }
+                m.at(931);
+                continue;
+            },
+            931 => {
+                // 931: ADD  rb-2, #-1, rb+1
+                m.at(931);
+                let a: Value = m.read_at(m.relative(-2)?)?;
+                let b: Value = -1;
+                let address = m.destination(m.relative(1)?)?;
+                m.store(address, a.checked_add(b)?, 935)?;
+                // 935: MUL  #942, #1, rb+0
+                m.at(935);
+                let a: Value = 942;
+                let b: Value = 1;
+                let address = m.destination(m.relative(0)?)?;
+                m.store(address, a.checked_mul(b)?, 939)?;
+                // 939: JT   #1, #922
+                m.at(939);
+                m.at(922);
+                continue;
+            },
+            942 => {
+                // 942: MUL  #1, rb+1, rb-1
+                m.at(942);
+                let a: Value = 1;
+                let b: Value = m.read_at(m.relative(1)?)?;
+                let address = m.destination(m.relative(-1)?)?;
+                m.store(address, a.checked_mul(b)?, 946)?;
+                // 946: ADD  rb-2, #-3, rb+1
+                m.at(946);
+                let a: Value = m.read_at(m.relative(-2)?)?;
+                let b: Value = -3;
+                let address = m.destination(m.relative(1)?)?;
+                m.store(address, a.checked_add(b)?, 950)?;
+                // 950: ADD  #0, #957, rb+0
+                m.at(950);
+                let a: Value = 0;
+                let b: Value = 957;
+                let address = m.destination(m.relative(0)?)?;
+                m.store(address, a.checked_add(b)?, 954)?;
+                // 954: JT   #1, #922
+                m.at(954);
+                m.at(922);
+                continue;
+            },
+            957 => {
+                // 957: ADD  rb+1, rb-1, rb-2
+                m.at(957);
+                let a: Value = m.read_at(m.relative(1)?)?;
+                let b: Value = m.read_at(m.relative(-1)?)?;
+                let address = m.destination(m.relative(-2)?)?;
+                m.store(address, a.checked_add(b)?, 961)?;
+                // 961: JF   #0, #968
+                m.at(961);
+                m.at(968);
+                continue;
+            },
+            964 => {
+                // 964: ADD  rb-2, #0, rb-2
+                m.at(964);
+                let a: Value = m.read_at(m.relative(-2)?)?;
+                let b: Value = 0;
+                let address = m.destination(m.relative(-2)?)?;
+                m.store(address, a.checked_add(b)?, 968)?;
+                m.at(968);
+                continue;
+            },
+            968 => {
+                // 968: RBO  #-3
+                m.at(968);
+                m.offset_base(-3)?;
+                // 970: JT   #1, rb+0
+                m.at(970);
+                m.at(m.address(m.read_at(m.relative(0)?)?)?);
+                continue;
+            },
+            _ => return None
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// Programs compiled to Rust by the transpiler.
+
+// The Day 9 program from the benchmarks, which spends nearly all of its time
+// in a recursive function.
+pub mod day_nine;
+
+// Outputs its input counting down to 1 by rewriting an OUT's parameter, so
+// the interpreter has to take over from it.
+pub mod self_modifying;
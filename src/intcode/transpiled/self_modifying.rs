@@ -0,0 +1,69 @@
+// Generated from an Intcode program by `cargo run --bin transpile`. Don't
+// edit it by hand, transpile the program again instead.
+
+use crate::intcode::Value;
+use crate::intcode::transpiler::{Compiled, Suspend};
+
+pub const PROGRAM: [Value; 17] = [
+    3, 16, 1001, 16, 0, 7, 104, 0, 1001, 16,
+    -1, 16, 1005, 16, 2, 99, 0
+];
+
+static CODE: [bool; 17] = [
+    true, true, true, true, true, true, true, true, true, true,
+    true, true, true, true, true, true, false
+];
+
+pub fn machine() -> Compiled {
+    Compiled::new(&PROGRAM, &CODE, execute)
+}
+
+fn execute(m: &mut Compiled) -> Option<Suspend> {
+    loop {
+        match m.pointer() {
+            0 => {
+                // 0: IN   [16]
+                m.at(0);
+                let address = m.destination(16)?;
+                let value = match m.input() {
+                    Some(value) => value,
+                    None => return Some(Suspend::Blocked)
+                };
+                m.store(address, value, 2)?;
+                m.at(2);
+                continue;
+            },
+            2 => {
+                // 2: ADD  [16], #0, [7]
+                m.at(2);
+                let a: Value = m.read_at(16)?;
+                let b: Value = 0;
+                let address = m.destination(7)?;
+                m.store(address, a.checked_add(b)?, 6)?;
+                // 6: OUT  #0
+                m.at(6);
+                m.output(0);
+                // 8: ADD  [16], #-1, [16]
+                m.at(8);
+                let a: Value = m.read_at(16)?;
+                let b: Value = -1;
+                let address = m.destination(16)?;
+                m.store(address, a.checked_add(b)?, 12)?;
+                // 12: JT   [16], #2
+                m.at(12);
+                if m.read_at(16)? != 0 {
+                    m.at(2);
+                    continue;
+                }
+                m.at(15);
+                continue;
+            },
+            15 => {
+                // 15: HLT
+                m.at(15);
+                return Some(Suspend::Halted);
+            },
+            _ => return None
+        }
+    }
+}
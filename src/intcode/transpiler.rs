@@ -0,0 +1,471 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use super::{Address, Instruction, Machine, MachineState, OperationalError, Opcode, Parameter,
+            ParameterMode, RunOutcome, Value};
+use super::control_flow::{ControlFlowGraph, Exit};
+use super::decompiler::constant_address;
+use super::disassembler::decode_at;
+use super::memory::DEFAULT_MEMORY_LIMIT;
+
+// Ahead of time compilation of Intcode programs into Rust, for programs that
+// get run enough to be worth it.
+//
+// Each basic block of the program becomes an arm of a match on the pointer,
+// with its instructions turned into straight line Rust, so there's no
+// fetching or decoding left at runtime. Anything the compiled code can't
+// handle hands the machine over to the interpreter at the instruction that
+// couldn't be handled, which then carries on as if it had been interpreting
+// all along. That covers:
+//
+// - writes to the compiled code, since it wouldn't change what runs
+// - jumps to addresses that don't start a block
+// - anything that would be an error, so the interpreter can report it
+//
+// Only programs over Value can be compiled, and only memory, the pointer,
+// the relative base and I/O carry over. There's no instruction counter,
+// breakpoints, tracing or any of the rest.
+
+// Why a compiled program stopped running. Compiled code returns None instead
+// when it needs the interpreter to take over.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Suspend {
+    Halted,
+    Blocked
+}
+
+type Execute = fn(&mut Compiled) -> Option<Suspend>;
+
+// A compiled program, with the same write, run and read as a Machine. The
+// rest of its methods are for the generated code to use.
+pub struct Compiled {
+    memory: Vec<Value>,
+    code: &'static [bool],
+    execute: Execute,
+    pointer: Address,
+    relative_base: Value,
+    state: MachineState,
+    input: VecDeque<Value>,
+    output: Vec<Value>,
+    // Set once the interpreter has taken over, after which everything goes
+    // to it.
+    interpreter: Option<Machine>
+}
+
+impl std::fmt::Debug for Compiled {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Compiled")
+            .field("pointer", &self.pointer)
+            .field("relative_base", &self.relative_base)
+            .field("state", &self.state)
+            .field("interpreting", &self.interpreting())
+            .finish()
+    }
+}
+
+impl Compiled {
+    pub fn new(program: &[Value], code: &'static [bool], execute: Execute) -> Compiled {
+        Compiled {
+            memory: program.to_vec(),
+            code,
+            execute,
+            pointer: 0,
+            relative_base: 0,
+            state: MachineState::Running,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            interpreter: None
+        }
+    }
+
+    pub fn write(&mut self, input: Value) {
+        match &mut self.interpreter {
+            Some(machine) => machine.write(input),
+            None => self.input.push_back(input)
+        }
+    }
+
+    pub fn read(&mut self) -> Vec<Value> {
+        let mut output = std::mem::take(&mut self.output);
+        if let Some(machine) = &mut self.interpreter {
+            output.extend(machine.read());
+        }
+        output
+    }
+
+    pub fn run(&mut self) -> Result<RunOutcome, OperationalError> {
+        if let Some(machine) = &mut self.interpreter {
+            return machine.run();
+        }
+        if self.state == MachineState::Halted {
+            return Ok(RunOutcome::Halted);
+        }
+
+        self.state = MachineState::Running;
+        match (self.execute)(self) {
+            Some(Suspend::Halted) => {
+                self.state = MachineState::Halted;
+                Ok(RunOutcome::Halted)
+            },
+            Some(Suspend::Blocked) => {
+                self.state = MachineState::Blocked;
+                Ok(RunOutcome::Blocked)
+            },
+            None => self.interpret().run()
+        }
+    }
+
+    pub fn state(&self) -> MachineState {
+        match &self.interpreter {
+            Some(machine) => machine.state(),
+            None => self.state
+        }
+    }
+
+    // Whether the interpreter has taken over.
+    pub fn interpreting(&self) -> bool {
+        self.interpreter.is_some()
+    }
+
+    fn interpret(&mut self) -> &mut Machine {
+        let mut machine = Machine::with_slots(std::mem::take(&mut self.memory));
+        machine.pointer = self.pointer;
+        machine.relative_base = self.relative_base;
+        machine.input = self.input.drain(..).collect();
+        self.interpreter.get_or_insert(machine)
+    }
+
+    pub fn pointer(&self) -> Address {
+        self.pointer
+    }
+
+    // Called before each instruction, so the interpreter knows where to
+    // start if the instruction can't be finished.
+    #[inline]
+    pub fn at(&mut self, address: Address) {
+        self.pointer = address;
+    }
+
+    #[inline]
+    pub fn relative(&self, offset: Value) -> Option<Address> {
+        self.address(self.relative_base.checked_add(offset)?)
+    }
+
+    #[inline]
+    pub fn address(&self, value: Value) -> Option<Address> {
+        if value < 0 { None } else { Some(value as Address) }
+    }
+
+    #[inline]
+    pub fn read_at(&self, address: Address) -> Option<Value> {
+        match self.memory.get(address) {
+            Some(value) => Some(*value),
+            None if address < DEFAULT_MEMORY_LIMIT => Some(0),
+            None => None
+        }
+    }
+
+    // Checks an address can be written to, before anything that can't be
+    // taken back like reading input.
+    #[inline]
+    pub fn destination(&self, address: Address) -> Option<Address> {
+        if address < DEFAULT_MEMORY_LIMIT { Some(address) } else { None }
+    }
+
+    // A write to the compiled code happens, and then the interpreter takes
+    // over from the next instruction, at next.
+    #[inline]
+    pub fn store(&mut self, address: Address, value: Value, next: Address) -> Option<()> {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        self.memory[address] = value;
+
+        if self.code.get(address) == Some(&true) {
+            self.pointer = next;
+            return None;
+        }
+        Some(())
+    }
+
+    #[inline]
+    pub fn input(&mut self) -> Option<Value> {
+        self.input.pop_front()
+    }
+
+    #[inline]
+    pub fn output(&mut self, value: Value) {
+        self.output.push(value);
+    }
+
+    #[inline]
+    pub fn offset_base(&mut self, change: Value) -> Option<()> {
+        self.relative_base = self.relative_base.checked_add(change)?;
+        Some(())
+    }
+}
+
+// Blocks start everywhere ControlFlowGraph finds, and also at every input,
+// so that a blocked program can pick up where it left off, and everywhere a
+// constant that's the address of an instruction gets stored. Programs jump
+// to those dynamically, whether they're addresses for calls to return to or
+// tables of places to jump to. That can find more code, so this goes round
+// until it stops finding any.
+fn blocks(slots: &[Value]) -> ControlFlowGraph {
+    let mut entries = BTreeSet::new();
+    loop {
+        let others: Vec<Address> = entries.iter().copied().collect();
+        let graph = ControlFlowGraph::with_entries(slots, 0, &others);
+
+        let before = entries.len();
+        for (address, instruction) in graph.blocks().flat_map(|block| block.instructions.iter()) {
+            if instruction.opcode == Opcode::Input {
+                entries.insert(*address);
+            }
+            if let Some(constant) = constant_address(instruction) {
+                if decode_at(slots, constant).is_some() {
+                    entries.insert(constant);
+                }
+            }
+        }
+        if entries.len() == before {
+            return graph;
+        }
+    }
+}
+
+fn operand(parameter: &Parameter) -> String {
+    match parameter.mode {
+        ParameterMode::Immediate => parameter.value.to_string(),
+        ParameterMode::Positional => format!("m.read_at({})?", parameter.value),
+        ParameterMode::Relative => format!("m.read_at(m.relative({})?)?", parameter.value)
+    }
+}
+
+fn destination(parameter: &Parameter) -> String {
+    match parameter.mode {
+        ParameterMode::Relative => format!("m.destination(m.relative({})?)?", parameter.value),
+        _ => format!("m.destination({})?", parameter.value)
+    }
+}
+
+fn values(values: &[String], per_line: usize) -> String {
+    values.chunks(per_line)
+        .map(|chunk| format!("    {}", chunk.join(", ")))
+        .collect::<Vec<String>>()
+        .join(",\n")
+}
+
+// Rust source for a module with the program in it as PROGRAM, and a
+// machine() that makes a Compiled to run it. The module uses the intcode
+// module from crate_path, which is `crate` for code going into this crate and
+// `aoc19` for anywhere else.
+pub fn transpile(slots: &[Value], crate_path: &str) -> String {
+    let graph = blocks(slots);
+
+    let mut code = vec![false; slots.len()];
+    for block in graph.blocks() {
+        for (address, instruction) in &block.instructions {
+            let end = (address + instruction.parameters().len() + 1).min(slots.len());
+            for covered in &mut code[*address..end] {
+                *covered = true;
+            }
+        }
+    }
+
+    let mut lines = vec![
+        "// Generated from an Intcode program by `cargo run --bin transpile`. Don't".to_string(),
+        "// edit it by hand, transpile the program again instead.".to_string(),
+        String::new(),
+        format!("use {}::intcode::Value;", crate_path),
+        format!("use {}::intcode::transpiler::{{Compiled, Suspend}};", crate_path),
+        String::new(),
+        format!("pub const PROGRAM: [Value; {}] = [", slots.len()),
+        values(&slots.iter().map(|v| v.to_string()).collect::<Vec<String>>(), 10),
+        "];".to_string(),
+        String::new(),
+        format!("static CODE: [bool; {}] = [", code.len()),
+        values(&code.iter().map(|c| c.to_string()).collect::<Vec<String>>(), 10),
+        "];".to_string(),
+        String::new(),
+        "pub fn machine() -> Compiled {".to_string(),
+        "    Compiled::new(&PROGRAM, &CODE, execute)".to_string(),
+        "}".to_string(),
+        String::new(),
+        "fn execute(m: &mut Compiled) -> Option<Suspend> {".to_string(),
+        "    loop {".to_string(),
+        "        match m.pointer() {".to_string()
+    ];
+
+    for block in graph.blocks() {
+        lines.push(format!("            {} => {{", block.start));
+        let code = block_code(&block.instructions, block.exit);
+        lines.extend(code.into_iter().map(|line| format!("                {}", line)));
+        lines.push("            },".to_string());
+    }
+
+    lines.extend(vec![
+        "            _ => return None".to_string(),
+        "        }".to_string(),
+        "    }".to_string(),
+        "}".to_string()
+    ]);
+    lines.join("\n") + "\n"
+}
+
+fn block_code(instructions: &[(Address, Instruction)], exit: Exit) -> Vec<String> {
+    let mut lines = Vec::new();
+    let last = instructions.len().saturating_sub(1);
+    let jumps = matches!(exit, Exit::Jump(_) | Exit::Branch { .. } | Exit::Dynamic { .. });
+
+    for (i, (address, instruction)) in instructions.iter().enumerate() {
+        let parameters = instruction.parameters();
+        let next = address + parameters.len() + 1;
+        lines.push(format!("// {}: {}", address, instruction));
+        lines.push(format!("m.at({});", address));
+
+        match instruction.opcode {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+                lines.push(format!("let a: Value = {};", operand(&parameters[0])));
+                lines.push(format!("let b: Value = {};", operand(&parameters[1])));
+                lines.push(format!("let address = {};", destination(&parameters[2])));
+                let value = match instruction.opcode {
+                    Opcode::Add => "a.checked_add(b)?",
+                    Opcode::Multiply => "a.checked_mul(b)?",
+                    Opcode::LessThan => "(a < b) as Value",
+                    _ => "(a == b) as Value"
+                };
+                lines.push(format!("m.store(address, {}, {})?;", value, next));
+            },
+            Opcode::Input => {
+                lines.push(format!("let address = {};", destination(&parameters[0])));
+                lines.push("let value = match m.input() {".to_string());
+                lines.push("    Some(value) => value,".to_string());
+                lines.push("    None => return Some(Suspend::Blocked)".to_string());
+                lines.push("};".to_string());
+                lines.push(format!("m.store(address, value, {})?;", next));
+            },
+            Opcode::Output => lines.push(format!("m.output({});", operand(&parameters[0]))),
+            Opcode::RelativeBaseOffset => {
+                lines.push(format!("m.offset_base({})?;", operand(&parameters[0])));
+            },
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse if i == last && jumps => {
+                let target = match parameters[1].mode {
+                    ParameterMode::Immediate => parameters[1].value.to_string(),
+                    _ => format!("m.address({})?", operand(&parameters[1]))
+                };
+                let jump = vec![format!("m.at({});", target), "continue;".to_string()];
+
+                // Immediate conditions at the end of a block are always true.
+                if parameters[0].mode == ParameterMode::Immediate {
+                    lines.extend(jump);
+                } else {
+                    let test = if instruction.opcode == Opcode::JumpIfTrue { "!=" } else { "==" };
+                    lines.push(format!("if {} {} 0 {{", operand(&parameters[0]), test));
+                    lines.extend(jump.into_iter().map(|line| format!("    {}", line)));
+                    lines.push("}".to_string());
+                }
+            },
+            // Jumps anywhere else are never taken.
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {},
+            Opcode::Halt => lines.push("return Some(Suspend::Halted);".to_string())
+        }
+    }
+
+    match exit {
+        Exit::Fallthrough(next) => {
+            lines.push(format!("m.at({});", next));
+            lines.push("continue;".to_string());
+        },
+        Exit::Jump(_) | Exit::Dynamic { not_taken: None } | Exit::Halt => {},
+        Exit::Branch { not_taken, .. } | Exit::Dynamic { not_taken: Some(not_taken) } => {
+            lines.push(format!("m.at({});", not_taken));
+            lines.push("continue;".to_string());
+        },
+        // The interpreter can report whatever's wrong there.
+        Exit::Invalid(address) => {
+            lines.push(format!("m.at({});", address));
+            lines.push("return None;".to_string());
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::transpiled::{day_nine, self_modifying};
+
+    // Regenerate these with the commands in the assertion messages if the
+    // transpiler changes.
+    #[test]
+    fn transpiled_programs_are_current() {
+        assert_eq!(include_str!("transpiled/day_nine.rs"), transpile(&day_nine::PROGRAM, "crate"),
+                   "cargo run --bin transpile -- --crate-path crate <day nine program> \
+                    > src/intcode/transpiled/day_nine.rs");
+        assert_eq!(include_str!("transpiled/self_modifying.rs"),
+                   transpile(&self_modifying::PROGRAM, "crate"),
+                   "cargo run --bin transpile -- --crate-path crate --assembly <program> \
+                    > src/intcode/transpiled/self_modifying.rs");
+    }
+
+    fn interpreted(program: &[Value], input: &[Value]) -> Result<(RunOutcome, Vec<Value>), OperationalError> {
+        let mut machine = Machine::from_slots(program.to_vec());
+        for value in input {
+            machine.write(*value);
+        }
+        let outcome = machine.run()?;
+        Ok((outcome, machine.read()))
+    }
+
+    fn compiled(mut machine: Compiled, input: &[Value]) -> Result<(RunOutcome, Vec<Value>), OperationalError> {
+        for value in input {
+            machine.write(*value);
+        }
+        let outcome = machine.run()?;
+        Ok((outcome, machine.read()))
+    }
+
+    #[test]
+    fn same_as_interpreter() -> Result<(), OperationalError> {
+        for input in &[1, 2] {
+            assert_eq!(interpreted(&day_nine::PROGRAM, &[*input])?,
+                       compiled(day_nine::machine(), &[*input])?);
+
+            let mut machine = day_nine::machine();
+            machine.write(*input);
+            machine.run()?;
+            assert!(!machine.interpreting());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn self_modifying_code() -> Result<(), OperationalError> {
+        let mut machine = self_modifying::machine();
+        machine.write(3);
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        assert_eq!(vec![3, 2, 1], machine.read());
+        assert!(machine.interpreting());
+
+        assert_eq!(interpreted(&self_modifying::PROGRAM, &[5])?,
+                   compiled(self_modifying::machine(), &[5])?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn blocking() -> Result<(), OperationalError> {
+        let mut machine = self_modifying::machine();
+        assert_eq!(RunOutcome::Blocked, machine.run()?);
+        assert_eq!(MachineState::Blocked, machine.state());
+        assert!(!machine.interpreting());
+
+        machine.write(1);
+        assert_eq!(RunOutcome::Halted, machine.run()?);
+        assert_eq!(vec![1], machine.read());
+
+        Ok(())
+    }
+}
@@ -1,50 +1,55 @@
 // The stubs for future days have a lot of these, we don't need the warnings.
 #![allow(unused_variables, unused_imports)]
 
-#[macro_use] extern crate itertools;
-
-mod problem;
-mod days;
-mod intcode;
-
 use std::io::Read;
 use std::fs::File;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use std::time::{Instant, Duration};
 
-use crate::problem::Problem;
-use crate::days::one::DayOne;
-use crate::days::two::DayTwo;
-use crate::days::three::DayThree;
-use crate::days::four::DayFour;
-use crate::days::five::DayFive;
-use crate::days::six::DaySix;
-use crate::days::seven::DaySeven;
-use crate::days::eight::DayEight;
-use crate::days::nine::DayNine;
-use crate::days::ten::DayTen;
-use crate::days::eleven::DayEleven;
-use crate::days::twelve::DayTwelve;
-use crate::days::thirteen::DayThirteen;
-use crate::days::fourteen::DayFourteen;
-use crate::days::fifteen::DayFifteen;
-use crate::days::sixteen::DaySixteen;
-use crate::days::seventeen::DaySeventeen;
-use crate::days::eighteen::DayEighteen;
-use crate::days::nineteen::DayNineteen;
-use crate::days::twenty::DayTwenty;
-use crate::days::twentyone::DayTwentyOne;
-use crate::days::twentytwo::DayTwentyTwo;
-use crate::days::twentythree::DayTwentyThree;
-use crate::days::twentyfour::DayTwentyFour;
-use crate::days::twentyfive::DayTwentyFive;
+use aoc19::problem::Problem;
+use aoc19::days::one::DayOne;
+use aoc19::days::two::DayTwo;
+use aoc19::days::three::DayThree;
+use aoc19::days::four::DayFour;
+use aoc19::days::five::DayFive;
+use aoc19::days::six::DaySix;
+use aoc19::days::seven::DaySeven;
+use aoc19::days::eight::DayEight;
+use aoc19::days::nine::DayNine;
+use aoc19::days::ten::DayTen;
+use aoc19::days::eleven::DayEleven;
+use aoc19::days::twelve::DayTwelve;
+use aoc19::days::thirteen::DayThirteen;
+use aoc19::days::fourteen::DayFourteen;
+use aoc19::days::fifteen::DayFifteen;
+use aoc19::days::sixteen::DaySixteen;
+use aoc19::days::seventeen::DaySeventeen;
+use aoc19::days::eighteen::DayEighteen;
+use aoc19::days::nineteen::DayNineteen;
+use aoc19::days::twenty::DayTwenty;
+use aoc19::days::twentyone::DayTwentyOne;
+use aoc19::days::twentytwo::DayTwentyTwo;
+use aoc19::days::twentythree::DayTwentyThree;
+use aoc19::days::twentyfour::DayTwentyFour;
+use aoc19::days::twentyfive::DayTwentyFive;
 
 #[derive(Debug, StructOpt)]
 struct Args {
-    day: Option<usize>
+    day: Option<usize>,
+
+    /// Run the day's Intcode program from a saved machine snapshot instead
+    /// of from its input file.
+    #[structopt(long = "resume", parse(from_os_str))]
+    resume: Option<PathBuf>,
+
+    /// Run every Intcode machine in day 7's amplifier search on its own
+    /// thread.
+    #[structopt(long = "threaded")]
+    threaded: bool
 }
 
-fn day2problem(day: usize) -> Option<Box<dyn Problem>> {
+fn day2problem(day: usize, threaded: bool) -> Option<Box<dyn Problem>> {
     match day {
         1 => Some(Box::new(DayOne{})),
         2 => Some(Box::new(DayTwo{})),
@@ -52,7 +57,7 @@ fn day2problem(day: usize) -> Option<Box<dyn Problem>> {
         4 => Some(Box::new(DayFour{})),
         5 => Some(Box::new(DayFive{})),
         6 => Some(Box::new(DaySix{})),
-        7 => Some(Box::new(DaySeven{})),
+        7 => Some(Box::new(DaySeven{ threaded })),
         8 => Some(Box::new(DayEight{})),
         9 => Some(Box::new(DayNine{})),
         10 => Some(Box::new(DayTen{})),
@@ -85,20 +90,31 @@ fn duration_str(duration: Duration) -> String {
     }
 }
 
-fn run_day(day: usize) -> std::io::Result<()> {
-    let open = File::open(format!("inputs/{}.txt", day));
+fn run_day(day: usize, resume: Option<&Path>, threaded: bool) -> std::io::Result<()> {
+    // Machine::from_str understands snapshots, so resuming is just a matter
+    // of handing the day a different input.
+    let open = match resume {
+        Some(snapshot) => File::open(snapshot),
+        None => File::open(format!("inputs/{}.txt", day))
+    };
     let input = match open {
         Ok(mut input_file) => {
             let mut buffer = String::new();
             input_file.read_to_string(&mut buffer)?;
             buffer
         },
-        Err(_) => {
-            format!("")
+        // A missing input is fine, lots of days don't have one, but a
+        // snapshot we can't open means we'd be solving the wrong thing.
+        Err(e) => match resume {
+            Some(snapshot) => {
+                eprintln!("Couldn't open snapshot {}: {}", snapshot.display(), e);
+                std::process::exit(1);
+            },
+            None => format!("")
         }
     };
 
-    let problem = day2problem(day).unwrap();
+    let problem = day2problem(day, threaded).unwrap();
 
     let title = format!(" Day {}: {} ", day, problem.name());
     println!("=========={:=<60}\n", title);
@@ -125,11 +141,15 @@ fn run_day(day: usize) -> std::io::Result<()> {
 fn main() -> std::io::Result<()> {
     let args = Args::from_args();
 
-    match args.day {
-        Some(day) => run_day(day),
-        None => {
+    match (args.day, args.resume) {
+        (Some(day), resume) => run_day(day, resume.as_deref(), args.threaded),
+        (None, Some(_)) => {
+            eprintln!("Resuming from a snapshot needs a day to run.");
+            std::process::exit(1);
+        },
+        (None, None) => {
             for day in 1..26 {
-                run_day(day)?;
+                run_day(day, None, args.threaded)?;
             }
             Ok(())
         }